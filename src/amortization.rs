@@ -0,0 +1,243 @@
+use crate::irr;
+
+// Loan amortization schedules with extra and lump-sum prepayments
+
+pub const LOAN_TYPES: &[&str] = &["fixed", "interest-only", "balloon", "equal-principal"];
+
+// Largest number of payment or amortization periods, e.g. 100 years of monthly payments
+pub const MAX_PAYMENTS: u32 = 1200;
+
+pub struct Loan {
+    pub kind: &'static str, // one of LOAN_TYPES
+    pub principal: f64,
+    pub rate: f64, // per payment period
+    pub payments: u32,
+    pub amortization_periods: u32, // a balloon loan pays as if amortizing over this many periods (0 = payments)
+    pub extra_payment: f64,        // extra principal every period
+    pub prepayments: Vec<(f64, f64)>, // (period, amount) lump sums
+}
+
+pub struct Row {
+    pub period: u32,
+    pub payment: f64, // scheduled payment plus prepayments
+    pub interest: f64,
+    pub principal: f64,
+    pub prepayment: f64,
+    pub balance: f64,
+}
+
+pub struct Schedule {
+    pub rows: Vec<Row>,
+    pub scheduled_payment: f64, // the first period's payment before prepayments
+    pub final_balance: f64,     // still owed after the last payment, i.e. the balloon
+    pub interest_without_prepayments: f64,
+}
+
+impl Schedule {
+    pub fn total_interest(&self) -> f64 {
+        self.rows.iter().map(|row| row.interest).sum()
+    }
+
+    // Every payment including the final balance
+    pub fn total_paid(&self) -> f64 {
+        self.rows.iter().map(|row| row.payment).sum::<f64>() + self.final_balance
+    }
+}
+
+fn level_payment(principal: f64, rate: f64, periods: u32) -> f64 {
+    if rate == 0.0 {
+        return principal / periods as f64;
+    }
+    principal * rate / (1.0 - (1.0 + rate).powi(-(periods as i32)))
+}
+
+pub fn schedule(loan: &Loan) -> Schedule {
+    let level = match loan.kind {
+        "balloon" if loan.amortization_periods > 0 => level_payment(loan.principal, loan.rate, loan.amortization_periods),
+        _ => level_payment(loan.principal, loan.rate, loan.payments),
+    };
+    let equal_principal = loan.principal / loan.payments as f64;
+    // The last payment of an amortizing loan clears whatever rounding has left
+    let fully_amortizing = matches!(loan.kind, "fixed" | "equal-principal");
+    let principal_due = |period: u32, balance: f64, interest: f64| {
+        let scheduled = match loan.kind {
+            "interest-only" => 0.0,
+            "equal-principal" => equal_principal,
+            _ => level - interest,
+        };
+        if period == loan.payments && fully_amortizing { balance } else { scheduled.min(balance) }
+    };
+
+    let mut rows: Vec<Row> = Vec::new();
+    let mut balance = loan.principal;
+    // The same loan without prepayments, run alongside for the interest they save
+    let (mut plain_balance, mut interest_without_prepayments) = (loan.principal, 0.0);
+    for period in 1..=loan.payments {
+        let plain_interest = plain_balance * loan.rate;
+        interest_without_prepayments += plain_interest;
+        plain_balance -= principal_due(period, plain_balance, plain_interest);
+        if rows.last().is_some_and(|row| row.balance == 0.0) {
+            continue;
+        }
+
+        let interest = balance * loan.rate;
+        let principal = principal_due(period, balance, interest);
+        balance -= principal;
+        let lump_sums: f64 = loan.prepayments.iter().filter(|(at, _)| *at as u32 == period).map(|(_, amount)| amount).sum();
+        let prepayment = (loan.extra_payment + lump_sums).min(balance);
+        balance -= prepayment;
+        rows.push(Row { period, payment: interest + principal + prepayment, interest, principal, prepayment, balance });
+    }
+    let scheduled_payment = rows.first().map(|row| row.interest + row.principal).unwrap_or(0.0);
+    Schedule { rows, scheduled_payment, final_balance: balance, interest_without_prepayments }
+}
+
+// Rate per period at which the payments repay the amount actually received after fees
+pub fn effective_rate(schedule: &Schedule, principal: f64, fees: f64) -> Option<f64> {
+    let mut flows = vec![-(principal - fees)];
+    flows.extend(schedule.rows.iter().map(|row| row.payment));
+    if let Some(last) = flows.last_mut() {
+        *last += schedule.final_balance;
+    }
+    irr::find_roots(|rate| irr::npv(&flows, rate)).first().map(|root| root.rate)
+}
+
+// Period by period, the final balance as a last "balloon" row when one is due
+pub fn table(schedule: &Schedule, max_rows: usize) -> String {
+    let mut text = format!(
+        "\n{:>6}  {:>14}  {:>14}  {:>14}  {:>14}  {:>16}\n",
+        "Period", "Payment", "Interest", "Principal", "Prepayment", "Balance"
+    );
+    let line = |row: &Row| {
+        format!(
+            "{:>6}  {:>14.2}  {:>14.2}  {:>14.2}  {:>14.2}  {:>16.2}\n",
+            row.period, row.payment, row.interest, row.principal, row.prepayment, row.balance
+        )
+    };
+    let rows = &schedule.rows;
+    if rows.len() <= max_rows {
+        rows.iter().for_each(|row| text.push_str(&line(row)));
+    } else {
+        let head = max_rows * 2 / 3;
+        let tail = max_rows - head;
+        rows[..head].iter().for_each(|row| text.push_str(&line(row)));
+        text.push_str(&format!("{:>6}  ... {} periods not shown; export the schedule to CSV to see them all\n", "", rows.len() - max_rows));
+        rows[rows.len() - tail..].iter().for_each(|row| text.push_str(&line(row)));
+    }
+    if schedule.final_balance > 0.0 {
+        text.push_str(&format!("{:>6}  {:>14.2}  {:>14}  {:>14.2}  {:>14}  {:>16.2}\n", "Due", schedule.final_balance, "", schedule.final_balance, "", 0.0));
+    }
+    text.push_str(&format!(
+        "{:>6}  {:>14.2}  {:>14.2}  {:>14.2}  {:>14.2}\n",
+        "Total",
+        schedule.total_paid(),
+        schedule.total_interest(),
+        rows.iter().map(|row| row.principal).sum::<f64>() + schedule.final_balance,
+        rows.iter().map(|row| row.prepayment).sum::<f64>()
+    ));
+    text
+}
+
+// 'none' or a .csv file to write the schedule to
+pub fn validate_export_file(text: &str) -> Result<(), String> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("none") || text.to_lowercase().ends_with(".csv") {
+        return Ok(());
+    }
+    Err(format!("'{}' is not 'none' or a .csv file", text))
+}
+
+// The schedule as CSV text, with the final balance as a last "balloon" row when one is due
+pub fn csv(schedule: &Schedule) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut write = |record: [String; 6]| writer.write_record(&record).map_err(|e| e.to_string());
+    write(["period", "payment", "interest", "principal", "prepayment", "balance"].map(String::from))?;
+    for row in &schedule.rows {
+        let amounts = [row.payment, row.interest, row.principal, row.prepayment, row.balance].map(|amount| format!("{:.2}", amount));
+        let [payment, interest, principal, prepayment, balance] = amounts;
+        write([row.period.to_string(), payment, interest, principal, prepayment, balance])?;
+    }
+    if schedule.final_balance > 0.0 {
+        let balloon = format!("{:.2}", schedule.final_balance);
+        write(["balloon".to_string(), balloon.clone(), "0.00".to_string(), balloon, "0.00".to_string(), "0.00".to_string()])?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    fn loan(kind: &'static str, principal: f64, rate: f64, payments: u32) -> Loan {
+        Loan { kind, principal, rate, payments, amortization_periods: 0, extra_payment: 0.0, prepayments: Vec::new() }
+    }
+
+    #[test]
+    fn fixed_loan_pays_a_level_payment() {
+        // 30-year mortgage of 100,000 at 6% a year paid monthly
+        let schedule = schedule(&loan("fixed", 100_000.0, 0.005, 360));
+        assert_close(schedule.scheduled_payment, 599.55, 0.005);
+        assert_eq!(schedule.rows.len(), 360);
+        assert!(schedule.rows[..359].iter().all(|row| (row.payment - schedule.scheduled_payment).abs() < 1e-9));
+        assert_close(schedule.rows[359].payment, schedule.scheduled_payment, 1e-6);
+        assert_eq!(schedule.final_balance, 0.0);
+        assert_close(schedule.total_paid(), 360.0 * schedule.scheduled_payment, 1e-6);
+    }
+
+    #[test]
+    fn equal_principal_final_row_clears_the_balance() {
+        let schedule = schedule(&loan("equal-principal", 1000.0, 0.01, 3));
+        let last = &schedule.rows[2];
+        assert_close(schedule.rows[0].payment, 1000.0 / 3.0 + 10.0, 1e-9);
+        assert_close(last.principal, 1000.0 / 3.0, 1e-9);
+        assert_close(last.interest, 1000.0 / 3.0 * 0.01, 1e-9);
+        assert_eq!(last.balance, 0.0);
+        assert_eq!(schedule.final_balance, 0.0);
+    }
+
+    #[test]
+    fn balloon_leaves_the_unamortized_balance() {
+        // Five years of payments on a 30-year amortization
+        let schedule = schedule(&Loan { amortization_periods: 360, ..loan("balloon", 100_000.0, 0.005, 60) });
+        let payment = level_payment(100_000.0, 0.005, 360);
+        assert_close(schedule.scheduled_payment, payment, 1e-9);
+        assert_close(schedule.final_balance, payment * (1.0 - 1.005f64.powi(-300)) / 0.005, 1e-6);
+        // Without amortization periods it amortizes over its payments
+        let full = super::schedule(&loan("balloon", 100_000.0, 0.005, 60));
+        assert_close(full.final_balance, 0.0, 1e-6);
+    }
+
+    #[test]
+    fn prepayments_are_capped_at_the_balance() {
+        let mut prepaid = loan("fixed", 1000.0, 0.0, 4);
+        prepaid.prepayments = vec![(2.0, 5000.0)];
+        let schedule = schedule(&prepaid);
+        assert_eq!(schedule.rows.len(), 2);
+        assert_close(schedule.rows[1].prepayment, 500.0, 1e-9);
+        assert_eq!(schedule.rows[1].balance, 0.0);
+        assert_close(schedule.total_paid(), 1000.0, 1e-9);
+    }
+
+    #[test]
+    fn interest_saved_by_prepaying() {
+        let plain = schedule(&loan("fixed", 10_000.0, 0.01, 24));
+        let prepaid = schedule(&Loan { extra_payment: 100.0, ..loan("fixed", 10_000.0, 0.01, 24) });
+        assert_close(prepaid.interest_without_prepayments, plain.total_interest(), 1e-9);
+        assert_close(plain.interest_without_prepayments, plain.total_interest(), 1e-9);
+        assert!(prepaid.rows.len() < 24);
+        assert!(prepaid.interest_without_prepayments - prepaid.total_interest() > 100.0);
+    }
+
+    #[test]
+    fn effective_rate_includes_fees() {
+        let schedule = schedule(&loan("fixed", 10_000.0, 0.005, 60));
+        assert_close(effective_rate(&schedule, 10_000.0, 0.0).unwrap(), 0.005, 1e-9);
+        // The payments are worth the amount received after fees at the effective rate
+        let rate = effective_rate(&schedule, 10_000.0, 200.0).unwrap();
+        assert!(rate > 0.005);
+        let received: f64 = schedule.rows.iter().map(|row| row.payment / (1.0 + rate).powi(row.period as i32)).sum();
+        assert_close(received, 9800.0, 1e-6);
+    }
+}
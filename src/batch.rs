@@ -0,0 +1,199 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value as Json};
+
+use crate::calculators::{self, Calculator, Field, FieldKind, Inputs, Output, Value, value_from_json};
+
+// Batch mode - evaluate one calculator per row of a CSV or JSON file
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format, String> {
+        match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref() {
+            Some("csv") => Ok(Format::Csv),
+            Some("json") => Ok(Format::Json),
+            _ => Err(format!("{}: expected a .csv or .json file", path.display())),
+        }
+    }
+}
+
+type Row = Map<String, Json>;
+
+pub struct Summary {
+    pub rows: usize,
+    pub failed: usize,
+}
+
+// Reads `input`, evaluates every row and writes the results to `output` (stdout when None)
+pub fn run(input: &Path, output: Option<&Path>, calculator: Option<&'static Calculator>) -> Result<Summary, String> {
+    let input_format = Format::from_path(input)?;
+    let output_format = match output {
+        Some(path) => Format::from_path(path)?,
+        None => input_format,
+    };
+
+    let rows = match input_format {
+        Format::Csv => read_csv(input)?,
+        Format::Json => read_json(input)?,
+    };
+
+    let results: Vec<Row> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let row = row.unwrap_or_else(|message| error_row(Map::new(), message));
+            evaluate_row(index + 1, row, calculator)
+        })
+        .collect();
+
+    let failed = results.iter().filter(|row| row.contains_key("error")).count();
+    let text = match output_format {
+        Format::Csv => write_csv(&results)?,
+        Format::Json => serde_json::to_string_pretty(&results).map_err(|e| e.to_string())? + "\n",
+    };
+    match output {
+        Some(path) => fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => print!("{}", text),
+    }
+
+    Ok(Summary { rows: results.len(), failed })
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Result<Row, String>>, String> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let headers = reader.headers().map_err(|e| format!("{}: {}", path.display(), e))?.clone();
+    Ok(reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| e.to_string())?;
+            Ok(headers
+                .iter()
+                .zip(record.iter())
+                .map(|(header, cell)| (header.to_string(), Json::String(cell.to_string())))
+                .collect())
+        })
+        .collect())
+}
+
+fn read_json(path: &Path) -> Result<Vec<Result<Row, String>>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let json: Json = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    match json {
+        Json::Array(items) => Ok(items
+            .into_iter()
+            .map(|item| match item {
+                Json::Object(row) => Ok(row),
+                _ => Err("row is not a JSON object".to_string()),
+            })
+            .collect()),
+        _ => Err(format!("{}: expected a JSON array of objects", path.display())),
+    }
+}
+
+fn error_row(mut row: Row, message: String) -> Row {
+    row.insert("error".to_string(), Json::String(message));
+    row
+}
+
+fn evaluate_row(number: usize, mut row: Row, calculator: Option<&'static Calculator>) -> Row {
+    if row.contains_key("error") {
+        eprintln!("row {}: {}", number, row["error"].as_str().unwrap_or_default());
+        return row;
+    }
+
+    match evaluate(&row, calculator) {
+        Ok((calc, outputs)) => {
+            if !row.contains_key("calculator") {
+                row.insert("calculator".to_string(), Json::String(calc.id.to_string()));
+            }
+            for output in outputs {
+                row.insert(output.name.to_string(), serde_json::json!(output.value));
+            }
+            row
+        }
+        Err(message) => {
+            eprintln!("row {}: {}", number, message);
+            error_row(row, message)
+        }
+    }
+}
+
+fn evaluate(row: &Row, calculator: Option<&'static Calculator>) -> Result<(&'static Calculator, Vec<Output>), String> {
+    let calc = match row_value(row, "calculator").and_then(Json::as_str) {
+        Some(name) => calculators::find(name).ok_or_else(|| format!("unknown calculator '{}'", name))?,
+        None => calculator.ok_or("no calculator column and no --calculator given")?,
+    };
+
+    let mut inputs = Inputs::new();
+    for field in calc.fields {
+        inputs.push(field, field_value(field, row_value(row, &field.key()))?);
+    }
+
+    let outputs = calculators::evaluate(calc, &inputs)?;
+    Ok((calc, outputs))
+}
+
+// Column lookup that ignores case and treats spaces and dashes as underscores
+fn row_value<'a>(row: &'a Row, key: &str) -> Option<&'a Json> {
+    row.iter()
+        .find(|(column, _)| column.trim().to_lowercase().replace([' ', '-'], "_") == key)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_null() && value.as_str().map(str::trim) != Some(""))
+}
+
+fn field_value(field: &Field, cell: Option<&Json>) -> Result<Value, String> {
+    // A list of pairs may be empty, so an empty cell is allowed
+    if let (FieldKind::Pairs(_), None) = (&field.kind, cell) {
+        return Ok(Value::Pairs(Vec::new()));
+    }
+    // Inputs with a default may be left out too
+    if let (Some(value), None) = (field.default_value(), cell) {
+        return Ok(value);
+    }
+    let cell = cell.ok_or_else(|| format!("missing '{}'", field.key()))?;
+    value_from_json(field, cell)
+}
+
+fn write_csv(rows: &[Row]) -> Result<String, String> {
+    // Columns are the union of every row's keys, in first-seen order
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        for key in row.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&columns).map_err(|e| e.to_string())?;
+    for row in rows {
+        let record: Vec<String> = columns.iter().map(|column| cell_text(row.get(*column))).collect();
+        writer.write_record(&record).map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+pub fn cell_text(value: Option<&Json>) -> String {
+    match value {
+        None | Some(Json::Null) => String::new(),
+        Some(Json::String(s)) => s.clone(),
+        Some(Json::Array(items)) => items
+            .iter()
+            .map(|item| match (item.get("amount"), item.get("date").and_then(Json::as_str), item.as_array()) {
+                (Some(amount), Some(date), _) => format!("{},{}", amount, date),
+                (_, _, Some(pair)) => pair.iter().map(Json::to_string).collect::<Vec<_>>().join(","),
+                _ => item.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(";"),
+        Some(other) => other.to_string(),
+    }
+}
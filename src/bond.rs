@@ -0,0 +1,212 @@
+use chrono::{Datelike, Months, NaiveDate};
+
+use crate::cashflows;
+use crate::daycount::DayCount;
+use crate::irr;
+
+// Fixed-coupon bonds: prices per 100 of face value from a yield compounded at the coupon
+// frequency, yields from prices, and accrued interest between coupon dates
+
+pub const FREQUENCIES: &[&str] = &["annual", "semiannual", "quarterly", "monthly"];
+
+pub struct Bond {
+    pub settlement: NaiveDate,
+    pub maturity: NaiveDate,
+    pub coupon_rate: f64,
+    pub frequency: u32, // coupons a year
+    pub day_count: DayCount,
+}
+
+pub fn frequency_from_choice(choice: &str) -> u32 {
+    match choice {
+        "annual" => 1,
+        "quarterly" => 4,
+        "monthly" => 12,
+        _ => 2,
+    }
+}
+
+// Dates are typed as for cash flows: YYYY-MM-DD or another common format
+pub fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    cashflows::parse_date(text).ok_or_else(|| format!("'{}' is not a date such as YYYY-MM-DD", text.trim()))
+}
+
+pub fn validate_date(text: &str) -> Result<(), String> {
+    parse_date(text).map(|_| ())
+}
+
+// 'none' or a date
+pub fn validate_optional_date(text: &str) -> Result<(), String> {
+    if text.trim().eq_ignore_ascii_case("none") {
+        return Ok(());
+    }
+    validate_date(text)
+}
+
+pub struct Quote {
+    pub clean: f64,
+    pub dirty: f64,
+    pub accrued: f64,
+}
+
+impl Bond {
+    pub fn new(settlement: NaiveDate, maturity: NaiveDate, coupon_rate: f64, frequency: u32, day_count: DayCount) -> Result<Bond, String> {
+        if maturity <= settlement {
+            return Err(format!("maturity {} must be after settlement {}", maturity, settlement));
+        }
+        if coupon_rate < 0.0 {
+            return Err("coupon-rate must not be negative".to_string());
+        }
+        Ok(Bond { settlement, maturity, coupon_rate, frequency, day_count })
+    }
+
+    fn coupon(&self) -> f64 {
+        100.0 * self.coupon_rate / self.frequency as f64
+    }
+
+    // Coupon dates counted back from maturity, so an end-of-month maturity keeps month ends
+    fn coupon_date(&self, periods_before_maturity: u32) -> NaiveDate {
+        let months = Months::new(12 / self.frequency * periods_before_maturity);
+        let date = self.maturity.checked_sub_months(months).expect("coupon date in range");
+        let month_end = self.maturity.succ_opt().is_some_and(|next| next.month() != self.maturity.month());
+        if !month_end {
+            return date;
+        }
+        let mut last = date;
+        while let Some(next) = last.succ_opt().filter(|next| next.month() == date.month()) {
+            last = next;
+        }
+        last
+    }
+
+    // The last coupon date on or before settlement and the coupon dates after it, to maturity
+    pub fn coupon_dates(&self) -> (NaiveDate, Vec<NaiveDate>) {
+        let mut remaining = Vec::new();
+        let mut periods = 0;
+        loop {
+            let date = self.coupon_date(periods);
+            if date <= self.settlement {
+                remaining.reverse();
+                return (date, remaining);
+            }
+            remaining.push(date);
+            periods += 1;
+        }
+    }
+
+    // Share of the current coupon period that has passed at settlement. For bonds the act/act
+    // choice means ACT/ACT ICMA, actual days over the days of the coupon period, not the ISDA
+    // year split used for dated cash flows; the other bases are year fraction times frequency.
+    fn accrual_fraction(&self, previous: NaiveDate, next: NaiveDate) -> f64 {
+        match self.day_count {
+            DayCount::ActActIsda => (self.settlement - previous).num_days() as f64 / (next - previous).num_days() as f64,
+            day_count => (day_count.year_fraction(previous, self.settlement) * self.frequency as f64).min(1.0),
+        }
+    }
+
+    pub fn accrued_interest(&self) -> f64 {
+        let (previous, remaining) = self.coupon_dates();
+        self.coupon() * self.accrual_fraction(previous, remaining[0])
+    }
+
+    // Coupons up to `redemption_date` plus the redemption, as (coupon periods from settlement, amount)
+    pub fn flows(&self, redemption_date: NaiveDate, redemption: f64) -> Vec<(f64, f64)> {
+        let (previous, remaining) = self.coupon_dates();
+        let offset = 1.0 - self.accrual_fraction(previous, remaining[0]);
+        let mut flows: Vec<(f64, f64)> = remaining
+            .iter()
+            .take_while(|date| **date <= redemption_date)
+            .enumerate()
+            .map(|(k, _)| (offset + k as f64, self.coupon()))
+            .collect();
+        // A call between coupon dates is redeemed part way through a period, with the interest
+        // accrued since the last coupon
+        let paid = flows.len();
+        let start = if paid == 0 { previous } else { remaining[paid - 1] };
+        let part = match remaining.get(paid) {
+            Some(next) if redemption_date > start => (redemption_date - start).num_days() as f64 / (*next - start).num_days() as f64,
+            _ => 0.0,
+        };
+        flows.push((offset + paid as f64 - 1.0 + part, redemption + self.coupon() * part));
+        flows
+    }
+
+    pub fn dirty_price(&self, flows: &[(f64, f64)], yield_rate: f64) -> f64 {
+        let per_period = 1.0 + yield_rate / self.frequency as f64;
+        flows.iter().map(|(periods, amount)| amount / per_period.powf(*periods)).sum()
+    }
+
+    pub fn price(&self, yield_rate: f64) -> Quote {
+        let flows = self.flows(self.maturity, 100.0);
+        let dirty = self.dirty_price(&flows, yield_rate);
+        let accrued = self.accrued_interest();
+        Quote { clean: dirty - accrued, dirty, accrued }
+    }
+
+    // Annual yield, compounded at the coupon frequency, that prices the flows to `redemption_date`
+    // at `clean_price`
+    pub fn yield_to(&self, clean_price: f64, redemption_date: NaiveDate, redemption: f64) -> Option<f64> {
+        let flows = self.flows(redemption_date, redemption);
+        let dirty = clean_price + self.accrued_interest();
+        let frequency = self.frequency as f64;
+        // Solved in the periodic rate, which the root scan covers from -99% up
+        irr::find_roots(|rate| self.dirty_price(&flows, rate * frequency) - dirty)
+            .first()
+            .map(|root| root.rate * frequency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    fn date(text: &str) -> NaiveDate {
+        parse_date(text).unwrap()
+    }
+
+    fn bond(settlement: &str, maturity: &str, coupon_rate: f64, day_count: DayCount) -> Bond {
+        Bond::new(date(settlement), date(maturity), coupon_rate, 2, day_count).unwrap()
+    }
+
+    #[test]
+    fn par_bond_prices_at_100() {
+        let quote = bond("2024-01-15", "2034-01-15", 0.05, DayCount::Thirty360Us).price(0.05);
+        assert_close(quote.clean, 100.0, 1e-9);
+        assert_close(quote.accrued, 0.0, 1e-12);
+    }
+
+    #[test]
+    fn matches_excel_price_and_yield() {
+        // The PRICE and YIELD examples of Excel's help, on the 30/360 US basis
+        let quote = bond("2008-02-15", "2017-11-15", 0.0575, DayCount::Thirty360Us).price(0.065);
+        assert_close(quote.clean, 94.63436, 1e-5);
+        assert_close(quote.accrued, 1.4375, 1e-9);
+        let bond = bond("2008-02-15", "2016-11-15", 0.0575, DayCount::Thirty360Us);
+        assert_close(bond.yield_to(95.04287, bond.maturity, 100.0).unwrap(), 0.065, 1e-7);
+    }
+
+    #[test]
+    fn month_end_coupons_with_act_act_accrual() {
+        let bond = bond("2024-03-15", "2025-02-28", 0.06, DayCount::ActActIsda);
+        let (previous, remaining) = bond.coupon_dates();
+        assert_eq!(previous, date("2024-02-29"));
+        assert_eq!(remaining, vec![date("2024-08-31"), date("2025-02-28")]);
+        // 15 of the period's 184 days have passed
+        assert_close(bond.accrued_interest(), 3.0 * 15.0 / 184.0, 1e-12);
+    }
+
+    #[test]
+    fn call_between_coupon_dates() {
+        let bond = bond("2024-01-15", "2034-01-15", 0.05, DayCount::Thirty360Us);
+        let flows = bond.flows(date("2025-04-15"), 101.0);
+        // Two coupons, then the call price with the interest accrued over 90 of 181 days
+        let part = 90.0 / 181.0;
+        assert_eq!(flows.len(), 3);
+        assert_close(flows[1].0, 2.0, 1e-12);
+        assert_close(flows[2].0, 2.0 + part, 1e-12);
+        assert_close(flows[2].1, 101.0 + 2.5 * part, 1e-12);
+        let clean = bond.dirty_price(&flows, 0.04) - bond.accrued_interest();
+        assert_close(bond.yield_to(clean, date("2025-04-15"), 101.0).unwrap(), 0.04, 1e-9);
+    }
+}
@@ -0,0 +1,2663 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ifch::*;
+use serde_json::{Map, Value as Json, json};
+
+use crate::amortization;
+use crate::cashflows;
+use crate::bond::{self, Bond};
+use crate::daycount::{self, Adjustment, Conventions, DayCount};
+use crate::duration;
+use crate::expr;
+use crate::irr;
+use crate::options::{self, Greeks, ImpliedVol, OptionKind, PathPayoff, Simulation, Tree, Vanilla};
+use crate::strategy::{self, Market};
+use crate::surface::{self, Surface};
+use crate::tvm;
+
+// Calculator registry shared by the interactive menus and the command line
+
+pub enum FieldKind {
+    Number,
+    Count,
+    CashFlows,
+    Choice(&'static [&'static str]), // one of a fixed list of lowercase options
+    Numbers,                         // list of numbers, e.g. periodic cash flows
+    Pairs(&'static str),             // possibly empty list of number pairs, e.g. "TIME,AMOUNT"
+    Text(fn(&str) -> Result<(), String>), // free text checked by the given function
+}
+
+// Domain rule a numeric input has to satisfy
+#[derive(Clone, Copy)]
+pub enum Rule {
+    Any,
+    Positive,
+    NonZero,
+    Fraction,
+}
+
+impl Rule {
+    pub fn check(self, value: f64) -> Result<(), String> {
+        if !value.is_finite() {
+            return Err("must be a finite number".to_string());
+        }
+        let ok = match self {
+            Rule::Any => true,
+            Rule::Positive => value > 0.0,
+            Rule::NonZero => value != 0.0,
+            Rule::Fraction => (0.0..=1.0).contains(&value),
+        };
+        if ok {
+            return Ok(());
+        }
+        Err(match self {
+            Rule::Positive => "must be greater than zero",
+            Rule::NonZero => "must not be zero",
+            _ => "must be between 0 and 1",
+        }
+        .to_string())
+    }
+}
+
+// Rule spanning several inputs of one calculator
+pub enum Check {
+    SumsToOne(&'static [&'static str]),
+}
+
+impl Check {
+    pub fn fields(&self) -> &'static [&'static str] {
+        match self {
+            Check::SumsToOne(names) => names,
+        }
+    }
+
+    pub fn apply(&self, inputs: &Inputs) -> Result<(), String> {
+        match self {
+            Check::SumsToOne(names) => {
+                let sum: f64 = names.iter().map(|name| inputs.num(name)).sum::<Result<f64, String>>()?;
+                if (sum - 1.0).abs() > 1e-4 {
+                    return Err(format!("weights {} must sum to 1 (got {:.4})", names.join(" + "), sum));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub struct Field {
+    pub name: &'static str, // kebab-case, doubles as the CLI flag
+    pub label: &'static str,
+    pub kind: FieldKind,
+    pub rule: Rule,
+    pub default: Option<&'static str>, // used when the input is not given, as it would be typed
+}
+
+impl Field {
+    const fn number(name: &'static str, label: &'static str) -> Field {
+        Field { name, label, kind: FieldKind::Number, rule: Rule::Any, default: None }
+    }
+
+    const fn count(name: &'static str, label: &'static str) -> Field {
+        Field { name, label, kind: FieldKind::Count, rule: Rule::Any, default: None }
+    }
+
+    const fn cashflows(name: &'static str, label: &'static str) -> Field {
+        Field { name, label, kind: FieldKind::CashFlows, rule: Rule::Any, default: None }
+    }
+
+    const fn choice(name: &'static str, label: &'static str, options: &'static [&'static str]) -> Field {
+        Field { name, label, kind: FieldKind::Choice(options), rule: Rule::Any, default: None }
+    }
+
+    const fn numbers(name: &'static str, label: &'static str) -> Field {
+        Field { name, label, kind: FieldKind::Numbers, rule: Rule::Any, default: None }
+    }
+
+    const fn pairs(name: &'static str, label: &'static str, format: &'static str) -> Field {
+        Field { name, label, kind: FieldKind::Pairs(format), rule: Rule::Any, default: None }
+    }
+
+    const fn text(name: &'static str, label: &'static str, validate: fn(&str) -> Result<(), String>) -> Field {
+        Field { name, label, kind: FieldKind::Text(validate), rule: Rule::Any, default: None }
+    }
+
+    const fn with_default(self, default: &'static str) -> Field {
+        Field { default: Some(default), ..self }
+    }
+
+    const fn positive(self) -> Field {
+        Field { rule: Rule::Positive, ..self }
+    }
+
+    const fn non_zero(self) -> Field {
+        Field { rule: Rule::NonZero, ..self }
+    }
+
+    const fn fraction(self) -> Field {
+        Field { rule: Rule::Fraction, ..self }
+    }
+
+    // snake_case key used in structured output
+    pub fn key(&self) -> String {
+        self.name.replace('-', "_")
+    }
+
+    pub fn default_value(&self) -> Option<Value> {
+        self.default.map(|default| value_from_json(self, &Json::String(default.to_string())).expect("field defaults are valid"))
+    }
+}
+
+#[derive(Clone)]
+pub enum Value {
+    Number(f64),
+    Count(u32),
+    CashFlows(Vec<(f64, String)>),
+    Choice(&'static str),
+    Numbers(Vec<f64>),
+    Pairs(Vec<(f64, f64)>),
+    Text(String),
+}
+
+impl Value {
+    pub fn to_json(&self) -> Json {
+        match self {
+            Value::Number(value) => json!(value),
+            Value::Count(value) => json!(value),
+            Value::CashFlows(flows) => flows
+                .iter()
+                .map(|(amount, date)| json!({ "amount": amount, "date": date }))
+                .collect(),
+            Value::Choice(option) => json!(option),
+            Value::Numbers(numbers) => json!(numbers),
+            Value::Pairs(pairs) => pairs.iter().map(|(x, y)| json!([x, y])).collect(),
+            Value::Text(text) => json!(text),
+        }
+    }
+}
+
+pub fn validate_value(field: &Field, value: &Value) -> Result<(), String> {
+    match value {
+        Value::Number(value) => field.rule.check(*value),
+        Value::Count(_) => Ok(()),
+        Value::CashFlows(flows) => validate_cashflows(flows),
+        Value::Choice(_) => Ok(()),
+        Value::Numbers(numbers) => validate_numbers(numbers),
+        Value::Pairs(pairs) => match pairs.iter().find(|(x, y)| !x.is_finite() || !y.is_finite()) {
+            Some((x, y)) => Err(format!("pair {},{} is not finite", x, y)),
+            None => Ok(()),
+        },
+        Value::Text(text) => match field.kind {
+            FieldKind::Text(validate) => validate(text),
+            _ => Ok(()),
+        },
+    }
+}
+
+pub fn validate_date(date: &str) -> Result<(), String> {
+    cashflows::parse_iso_date(date)
+        .map(|_| ())
+        .ok_or_else(|| format!("'{}' is not a valid YYYY-MM-DD date", date))
+}
+
+pub fn validate_cashflows(flows: &[(f64, String)]) -> Result<(), String> {
+    if flows.is_empty() {
+        return Err("needs at least one cash flow".to_string());
+    }
+    for (amount, date) in flows {
+        if !amount.is_finite() {
+            return Err(format!("amount {} is not a finite number", amount));
+        }
+        validate_date(date)?;
+    }
+    Ok(())
+}
+
+// Reads a value from JSON (history records, batch rows); numbers may also be given as strings
+pub fn value_from_json(field: &Field, json: &Json) -> Result<Value, String> {
+    let key = field.key();
+    match field.kind {
+        FieldKind::Number => match json {
+            Json::String(s) => expr::evaluate_constant(s).map(Value::Number).map_err(|e| format!("invalid number '{}' for '{}': {}", s.trim(), key, e)),
+            _ => json.as_f64().map(Value::Number).ok_or_else(|| format!("invalid number {} for '{}'", json, key)),
+        },
+        FieldKind::Count => match json {
+            Json::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()).map(Value::Count),
+            Json::String(s) => s.trim().parse::<u32>().ok().map(Value::Count),
+            _ => None,
+        }
+        .ok_or_else(|| format!("invalid count {} for '{}'", json, key)),
+        FieldKind::CashFlows => cashflows_from_json(json).map_err(|e| format!("invalid cash flows for '{}': {}", key, e)),
+        FieldKind::Choice(options) => json
+            .as_str()
+            .and_then(|s| parse_choice(options, s).ok())
+            .map(Value::Choice)
+            .ok_or_else(|| format!("invalid {} {} (expected {})", key, json, options.join(", "))),
+        FieldKind::Numbers => numbers_from_json(json).map_err(|e| format!("invalid numbers for '{}': {}", key, e)),
+        FieldKind::Pairs(format) => pairs_from_json(json).map_err(|e| format!("invalid {} for '{}': {}", format, key, e)),
+        FieldKind::Text(_) => match json {
+            Json::String(s) => Ok(Value::Text(s.trim().to_string())),
+            _ => Err(format!("invalid {} {} (expected text)", key, json)),
+        },
+    }
+}
+
+pub fn validate_numbers(numbers: &[f64]) -> Result<(), String> {
+    if numbers.is_empty() {
+        return Err("needs at least one number".to_string());
+    }
+    match numbers.iter().find(|number| !number.is_finite()) {
+        Some(number) => Err(format!("{} is not a finite number", number)),
+        None => Ok(()),
+    }
+}
+
+// Numbers are either "X;X;X" or a JSON array
+fn numbers_from_json(json: &Json) -> Result<Value, String> {
+    let numbers = match json {
+        Json::String(s) => parse_numbers(s)?,
+        Json::Array(items) => items
+            .iter()
+            .map(|item| item.as_f64().ok_or_else(|| format!("{} is not a number", item)))
+            .collect::<Result<Vec<_>, String>>()?,
+        _ => return Err("expected a string or an array".to_string()),
+    };
+    Ok(Value::Numbers(numbers))
+}
+
+pub fn parse_numbers(s: &str) -> Result<Vec<f64>, String> {
+    s.split(';')
+        .filter(|number| !number.trim().is_empty())
+        .map(parse_number)
+        .collect()
+}
+
+// A number in a batch file or on the command line: an expression like `5%`, `1.2m` or
+// `$1,234.56` as at the prompts, but without `ans` or saved variables
+fn parse_number(s: &str) -> Result<f64, String> {
+    expr::evaluate_constant(s).map_err(|e| format!("invalid number '{}': {}", s.trim(), e))
+}
+
+// Pairs are either "X,Y;X,Y" or a JSON array of [X, Y] arrays
+fn pairs_from_json(json: &Json) -> Result<Value, String> {
+    let pairs = match json {
+        Json::String(s) => parse_pairs(s)?,
+        Json::Array(items) => items
+            .iter()
+            .map(|item| match item.as_array().map(|pair| pair.as_slice()) {
+                Some([x, y]) => x.as_f64().zip(y.as_f64()).ok_or_else(|| format!("{} is not a pair of numbers", item)),
+                _ => Err(format!("{} is not a pair of numbers", item)),
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+        _ => return Err("expected a string or an array".to_string()),
+    };
+    Ok(Value::Pairs(pairs))
+}
+
+// Parses "X,Y;X,Y"; an empty string is an empty list
+pub fn parse_pairs(s: &str) -> Result<Vec<(f64, f64)>, String> {
+    s.split(';').filter(|pair| !pair.trim().is_empty()).map(parse_pair).collect()
+}
+
+pub fn parse_pair(s: &str) -> Result<(f64, f64), String> {
+    let (x, y) = s.split_once(',').ok_or_else(|| format!("expected two numbers separated by ',' but got '{}'", s.trim()))?;
+    Ok((parse_number(x)?, parse_number(y)?))
+}
+
+// Matches `s` case-insensitively against a choice field's options
+pub fn parse_choice(options: &'static [&'static str], s: &str) -> Result<&'static str, String> {
+    let s = s.trim().to_lowercase();
+    options
+        .iter()
+        .find(|option| **option == s)
+        .copied()
+        .ok_or_else(|| format!("'{}' is not one of {}", s, options.join(", ")))
+}
+
+// Cash flows are either "AMOUNT,DATE;AMOUNT,DATE" or a JSON array of {"amount", "date"} objects
+fn cashflows_from_json(json: &Json) -> Result<Value, String> {
+    let flows = match json {
+        Json::String(s) => s
+            .split(';')
+            .filter(|flow| !flow.trim().is_empty())
+            .map(parse_cashflow)
+            .collect::<Result<Vec<_>, _>>()?,
+        Json::Array(items) => items
+            .iter()
+            .map(|item| {
+                let amount = item["amount"].as_f64().ok_or("cash flow without a numeric amount")?;
+                let date = item["date"].as_str().ok_or("cash flow without a date")?;
+                Ok((amount, date.to_string()))
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+        _ => return Err("expected a string or an array".to_string()),
+    };
+    Ok(Value::CashFlows(flows))
+}
+
+// Parses a single `AMOUNT,YYYY-MM-DD` cash flow
+pub fn parse_cashflow(s: &str) -> Result<(f64, String), String> {
+    let (amount, date) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected AMOUNT,YYYY-MM-DD but got '{}'", s))?;
+    let amount = expr::evaluate_constant(amount).map_err(|e| format!("invalid amount '{}': {}", amount.trim(), e))?;
+    Ok((amount, date.trim().to_string()))
+}
+
+pub struct Inputs {
+    values: Vec<(&'static Field, Value)>,
+}
+
+impl Inputs {
+    pub fn new() -> Inputs {
+        Inputs { values: Vec::new() }
+    }
+
+    pub fn push(&mut self, field: &'static Field, value: Value) {
+        self.values.push((field, value));
+    }
+
+    pub fn set(&mut self, field: &'static Field, value: Value) {
+        match self.values.iter_mut().find(|(existing, _)| existing.name == field.name) {
+            Some(entry) => entry.1 = value,
+            None => self.push(field, value),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(&'static Field, Value)> {
+        self.values.iter()
+    }
+
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.values
+            .iter()
+            .find(|(field, _)| field.name == name)
+            .map(|(_, value)| value)
+    }
+
+    fn get(&self, name: &str) -> Result<&Value, String> {
+        self.value(name).ok_or_else(|| format!("missing input '{}'", name))
+    }
+
+    pub fn num(&self, name: &str) -> Result<f64, String> {
+        match self.get(name)? {
+            Value::Number(value) => Ok(*value),
+            Value::Count(value) => Ok(*value as f64),
+            _ => Err(format!("input '{}' is not a number", name)),
+        }
+    }
+
+    pub fn count(&self, name: &str) -> Result<u32, String> {
+        match self.get(name)? {
+            Value::Count(value) => Ok(*value),
+            _ => Err(format!("input '{}' is not a count", name)),
+        }
+    }
+
+    pub fn choice(&self, name: &str) -> Result<&'static str, String> {
+        match self.get(name)? {
+            Value::Choice(option) => Ok(option),
+            _ => Err(format!("input '{}' is not a choice", name)),
+        }
+    }
+
+    pub fn numbers(&self, name: &str) -> Result<&[f64], String> {
+        match self.get(name)? {
+            Value::Numbers(numbers) => Ok(numbers),
+            _ => Err(format!("input '{}' is not a list of numbers", name)),
+        }
+    }
+
+    pub fn pairs(&self, name: &str) -> Result<&[(f64, f64)], String> {
+        match self.get(name)? {
+            Value::Pairs(pairs) => Ok(pairs),
+            _ => Err(format!("input '{}' is not a list of pairs", name)),
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Result<&str, String> {
+        match self.get(name)? {
+            Value::Text(text) => Ok(text),
+            _ => Err(format!("input '{}' is not text", name)),
+        }
+    }
+
+    pub fn cashflows(&self, name: &str) -> Result<Vec<(f64, &str)>, String> {
+        match self.get(name)? {
+            Value::CashFlows(flows) => Ok(flows.iter().map(|(amt, date)| (*amt, date.as_str())).collect()),
+            _ => Err(format!("input '{}' is not a cash flow list", name)),
+        }
+    }
+}
+
+pub struct Output {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub value: f64,
+    pub decimals: usize,
+}
+
+impl Output {
+    fn new(name: &'static str, label: &'static str, value: f64) -> Output {
+        Output { name, label, value, decimals: 2 }
+    }
+
+    fn precise(name: &'static str, label: &'static str, value: f64, decimals: usize) -> Output {
+        Output { name, label, value, decimals }
+    }
+
+    // The value rounded for display; infinite values such as an uncapped profit read "unlimited"
+    pub fn display(&self) -> String {
+        if self.value.is_infinite() {
+            return "unlimited".to_string();
+        }
+        format!("{:.*}", self.decimals, self.value)
+    }
+}
+
+// Outputs as "label: value" lines; `call_x`/`put_x` pairs are gathered into a Call/Put table
+fn format_outputs(outputs: &[Output]) -> String {
+    let put_of = |call: &Output| {
+        let name = call.name.strip_prefix("call_")?;
+        outputs.iter().find(|output| output.name.strip_prefix("put_") == Some(name))
+    };
+    let pairs: Vec<(&Output, &Output)> = outputs
+        .iter()
+        .filter_map(|output| put_of(output).map(|put| (output, put)))
+        .collect();
+
+    let mut text = String::new();
+    if !pairs.is_empty() {
+        let row_label = |call: &Output| call.label.strip_prefix("Call ").unwrap_or(call.label).to_string();
+        let width = pairs.iter().map(|(call, _)| row_label(call).len()).max().unwrap_or(0);
+        text.push_str(&format!("{:<width$} {:>14} {:>14}\n", "", "Call", "Put", width = width));
+        for (call, put) in &pairs {
+            text.push_str(&format!("{:<width$} {:>14} {:>14}\n", row_label(call), call.display(), put.display(), width = width));
+        }
+    }
+    let paired = |output: &Output| pairs.iter().any(|(call, put)| call.name == output.name || put.name == output.name);
+    for output in outputs.iter().filter(|output| !paired(output)) {
+        text.push_str(&format!("{}: {}\n", output.label, output.display()));
+    }
+    text
+}
+
+// Text shown for a successful calculation: the outputs followed by the calculator's report
+pub fn format_result(evaluation: &Evaluation) -> String {
+    format_outputs(&evaluation.outputs) + &evaluation.report
+}
+
+// One-line display of a calculation's outputs, as shown in the history.
+// Long result sets such as Greeks are cut after the first few outputs.
+pub fn summary(outputs: &[Output]) -> String {
+    const SHOWN: usize = 4;
+    let mut text = outputs
+        .iter()
+        .take(SHOWN)
+        .map(|output| format!("{} = {}", output.label, output.display()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if outputs.len() > SHOWN {
+        text.push_str(", ...");
+    }
+    text
+}
+
+// Structured record of one calculation with every named input and full-precision output
+pub fn result_json(calc: &Calculator, inputs: &Inputs, result: &Result<Vec<Output>, String>) -> Json {
+    let input_map: Map<String, Json> = inputs.iter().map(|(field, value)| (field.key(), value.to_json())).collect();
+    let mut record = json!({
+        "calculator": calc.id,
+        "inputs": input_map,
+    });
+    match result {
+        Ok(outputs) => {
+            let output_map: Map<String, Json> =
+                outputs.iter().map(|output| (output.name.to_string(), json!(output.value))).collect();
+            record["outputs"] = Json::Object(output_map);
+        }
+        Err(message) => record["error"] = json!(message),
+    }
+    record
+}
+
+pub struct Calculator {
+    pub id: &'static str,
+    pub function: Option<&'static str>, // the ifch function the calculator wraps, if any
+    pub group: &'static str,
+    pub command: &'static str,
+    pub title: &'static str,
+    pub fields: &'static [Field],
+    pub checks: &'static [Check],
+    pub run: fn(&Inputs) -> Result<Vec<Output>, String>,
+    // Runs in place of `run` where the results are shown, working out the outputs and the text
+    // under them (a chart, schedule or warnings) in one pass
+    pub report: Option<Report>,
+}
+
+type Report = fn(&Inputs) -> Result<Evaluation, String>;
+
+// Outputs with the text shown under them
+pub struct Evaluation {
+    pub outputs: Vec<Output>,
+    pub report: String,
+    pub files: Vec<(PathBuf, String)>, // left for the front end to write, e.g. an exported schedule
+}
+
+impl Evaluation {
+    pub fn new(outputs: Vec<Output>, report: String) -> Evaluation {
+        Evaluation { outputs, report, files: Vec::new() }
+    }
+}
+
+// Writes the files of an evaluation, which only a fresh interactive run or a text command line
+// run does: history re-runs leave them alone, and batch rows and JSON runs reject them
+pub fn write_files(evaluation: &Evaluation) -> Result<(), String> {
+    for (path, contents) in &evaluation.files {
+        fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+// Command line groups, in menu order
+pub const GROUPS: &[(&str, &str)] = &[
+    ("ratio", "Liquidity, profitability, leverage, activity and valuation ratios"),
+    ("tvm", "Time value of money"),
+    ("build-up", "Free cash flow to the firm build ups"),
+    ("wacc", "Cost of capital"),
+    ("valuation", "Valuation models"),
+    ("option", "Option pricing"),
+    ("bond", "Bond pricing and yields"),
+];
+
+pub static CALCULATORS: &[&Calculator] = &[
+    &CURRENT_RATIO,
+    &QUICK_RATIO,
+    &ACID_TEST_RATIO,
+    &CASH_RATIO,
+    &GROSS_MARGIN,
+    &OPERATING_MARGIN,
+    &NET_MARGIN,
+    &RETURN_ON_ASSETS,
+    &RETURN_ON_EQUITY,
+    &DEBT_TO_EQUITY_RATIO,
+    &DEBT_RATIO,
+    &EBIT_INTEREST_COVERAGE,
+    &INVENTORY_TURNOVER,
+    &RECEIVABLES_TURNOVER,
+    &ASSET_TURNOVER,
+    &PRICE_TO_EARNINGS_RATIO,
+    &PRICE_TO_BOOK_RATIO,
+    &DIVIDEND_YIELD,
+    &XNPV,
+    &XIRR,
+    &PERIODIC_CASHFLOWS,
+    &TVM_PRESENT_VALUE,
+    &TVM_FUTURE_VALUE,
+    &TVM_PAYMENT,
+    &TVM_PERIODS,
+    &TVM_RATE_SOLVER,
+    &PERPETUITY,
+    &LOAN_AMORTIZATION,
+    &CASHFLOW_DURATION,
+    &FCFF_NI,
+    &FCFF_CFO,
+    &FCFF_EBIT,
+    &FCFF_EBITDA,
+    &WACC_COE,
+    &COE,
+    &WACC_BETA,
+    &MRP,
+    &EQUITY_BETA,
+    &ASSET_BETA,
+    &GGM_P1,
+    &GGM_P2,
+    &BSM,
+    &BSM_IMPLIED_VOL,
+    &BINOMIAL_TREE,
+    &TRINOMIAL_TREE,
+    &MONTE_CARLO_ASIAN,
+    &MONTE_CARLO_LOOKBACK,
+    &MONTE_CARLO_BARRIER,
+    &BLACK76,
+    &BLACK76_IMPLIED_VOL,
+    &BACHELIER,
+    &BACHELIER_IMPLIED_VOL,
+    &STRATEGY,
+    &PUT_CALL_PARITY,
+    &VOL_SURFACE,
+    &BOND_PRICE,
+    &BOND_YIELD,
+    &BOND_DURATION,
+];
+
+impl Calculator {
+    pub fn field(&self, name: &str) -> &'static Field {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .unwrap_or_else(|| panic!("{} has no input '{}'", self.id, name))
+    }
+}
+
+// Checks every input against its rule and the calculator's cross-field checks
+pub fn validate(calc: &Calculator, inputs: &Inputs) -> Result<(), String> {
+    for (field, value) in inputs.iter() {
+        validate_value(field, value).map_err(|e| format!("{} {}", field.key(), e))?;
+    }
+    for check in calc.checks {
+        check.apply(inputs)?;
+    }
+    Ok(())
+}
+
+// Validates and runs a calculator
+pub fn evaluate(calc: &Calculator, inputs: &Inputs) -> Result<Vec<Output>, String> {
+    validate(calc, inputs)?;
+    (calc.run)(inputs)
+}
+
+// As `evaluate`, with the calculator's report for showing the result
+pub fn evaluate_with_report(calc: &Calculator, inputs: &Inputs) -> Result<Evaluation, String> {
+    validate(calc, inputs)?;
+    match calc.report {
+        Some(report) => report(inputs),
+        None => Ok(Evaluation::new((calc.run)(inputs)?, String::new())),
+    }
+}
+
+// Looks a calculator up by its id or by the name of the ifch function it wraps
+pub fn find(name: &str) -> Option<&'static Calculator> {
+    CALCULATORS
+        .iter()
+        .copied()
+        .find(|calc| calc.id == name || calc.function == Some(name))
+}
+
+// Ratios - Liquidity
+
+pub const QUICK_RATIO: Calculator = Calculator {
+    id: "quick_ratio",
+    function: Some("quick_r"),
+    group: "ratio",
+    command: "quick",
+    title: "Quick Ratio",
+    fields: &[
+        Field::number("current-assets", "current assets"),
+        Field::number("inventory", "inventory"),
+        Field::number("current-liabilities", "current liabilities").non_zero(),
+    ],
+    checks: &[],
+    run: run_quick_ratio,
+    report: None,
+};
+
+fn run_quick_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let ratio = quick_r(inputs.num("current-assets")?, inputs.num("inventory")?, inputs.num("current-liabilities")?);
+    Ok(vec![Output::new("quick_ratio", "Quick Ratio", ratio)])
+}
+
+pub const ACID_TEST_RATIO: Calculator = Calculator {
+    id: "acid_test_ratio",
+    function: Some("acid_r"),
+    group: "ratio",
+    command: "acid-test",
+    title: "Acid Test Ratio",
+    fields: &[
+        Field::number("cash", "cash"),
+        Field::number("inventory", "inventory"),
+        Field::number("accounts-receivable", "accounts receivable"),
+        Field::number("current-liabilities", "current liabilities").non_zero(),
+    ],
+    checks: &[],
+    run: run_acid_test_ratio,
+    report: None,
+};
+
+fn run_acid_test_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let ratio = acid_r(
+        inputs.num("cash")?,
+        inputs.num("inventory")?,
+        inputs.num("accounts-receivable")?,
+        inputs.num("current-liabilities")?,
+    );
+    Ok(vec![Output::new("acid_test_ratio", "Acid Test Ratio", ratio)])
+}
+
+pub const CASH_RATIO: Calculator = Calculator {
+    id: "cash_ratio",
+    function: Some("cash_r"),
+    group: "ratio",
+    command: "cash",
+    title: "Cash Ratio",
+    fields: &[
+        Field::number("cash-and-equivalents", "cash and equivalents"),
+        Field::number("current-liabilities", "current liabilities").non_zero(),
+    ],
+    checks: &[],
+    run: run_cash_ratio,
+    report: None,
+};
+
+fn run_cash_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let ratio = cash_r(inputs.num("cash-and-equivalents")?, inputs.num("current-liabilities")?);
+    Ok(vec![Output::new("cash_ratio", "Cash Ratio", ratio)])
+}
+
+pub const CURRENT_RATIO: Calculator = Calculator {
+    id: "current_ratio",
+    function: Some("current_r"),
+    group: "ratio",
+    command: "current",
+    title: "Current Ratio",
+    fields: &[
+        Field::number("current-assets", "current assets"),
+        Field::number("current-liabilities", "current liabilities").non_zero(),
+    ],
+    checks: &[],
+    run: run_current_ratio,
+    report: None,
+};
+
+fn run_current_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let ratio = current_r(inputs.num("current-assets")?, inputs.num("current-liabilities")?);
+    Ok(vec![Output::new("current_ratio", "Current Ratio", ratio)])
+}
+
+// Ratios - Profitability
+
+pub const GROSS_MARGIN: Calculator = Calculator {
+    id: "gross_margin",
+    function: Some("gross_m"),
+    group: "ratio",
+    command: "gross-margin",
+    title: "Gross Margin",
+    fields: &[
+        Field::number("gross-profit", "gross profit"),
+        Field::number("revenue", "revenue").non_zero(),
+    ],
+    checks: &[],
+    run: run_gross_margin,
+    report: None,
+};
+
+fn run_gross_margin(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let margin = gross_m(inputs.num("gross-profit")?, inputs.num("revenue")?);
+    Ok(vec![Output::new("gross_margin", "Gross Margin", margin)])
+}
+
+pub const OPERATING_MARGIN: Calculator = Calculator {
+    id: "operating_margin",
+    function: Some("operating_m"),
+    group: "ratio",
+    command: "operating-margin",
+    title: "Operating Margin",
+    fields: &[
+        Field::number("operating-income", "operating income"),
+        Field::number("revenue", "revenue").non_zero(),
+    ],
+    checks: &[],
+    run: run_operating_margin,
+    report: None,
+};
+
+fn run_operating_margin(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let margin = operating_m(inputs.num("operating-income")?, inputs.num("revenue")?);
+    Ok(vec![Output::new("operating_margin", "Operating Margin", margin)])
+}
+
+pub const NET_MARGIN: Calculator = Calculator {
+    id: "net_margin",
+    function: Some("net_m"),
+    group: "ratio",
+    command: "net-margin",
+    title: "Net Margin",
+    fields: &[
+        Field::number("net-income", "net income"),
+        Field::number("revenue", "revenue").non_zero(),
+    ],
+    checks: &[],
+    run: run_net_margin,
+    report: None,
+};
+
+fn run_net_margin(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let margin = net_m(inputs.num("net-income")?, inputs.num("revenue")?);
+    Ok(vec![Output::new("net_margin", "Net Margin", margin)])
+}
+
+pub const RETURN_ON_ASSETS: Calculator = Calculator {
+    id: "return_on_assets",
+    function: Some("r_o_a"),
+    group: "ratio",
+    command: "roa",
+    title: "Return on Assets (ROA)",
+    fields: &[
+        Field::number("net-income", "net income"),
+        Field::number("total-assets", "total assets").non_zero(),
+    ],
+    checks: &[],
+    run: run_return_on_assets,
+    report: None,
+};
+
+fn run_return_on_assets(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let roa = r_o_a(inputs.num("net-income")?, inputs.num("total-assets")?);
+    Ok(vec![Output::new("roa", "ROA", roa)])
+}
+
+pub const RETURN_ON_EQUITY: Calculator = Calculator {
+    id: "return_on_equity",
+    function: Some("r_o_e"),
+    group: "ratio",
+    command: "roe",
+    title: "Return on Equity (ROE)",
+    fields: &[
+        Field::number("net-income", "net income"),
+        Field::number("shareholders-equity", "shareholders' equity").non_zero(),
+    ],
+    checks: &[],
+    run: run_return_on_equity,
+    report: None,
+};
+
+fn run_return_on_equity(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let roe = r_o_e(inputs.num("net-income")?, inputs.num("shareholders-equity")?);
+    Ok(vec![Output::new("roe", "ROE", roe)])
+}
+
+// Ratios - Leverage
+
+pub const DEBT_TO_EQUITY_RATIO: Calculator = Calculator {
+    id: "debt_to_equity_ratio",
+    function: Some("d_t_e"),
+    group: "ratio",
+    command: "debt-to-equity",
+    title: "Debt to Equity Ratio",
+    fields: &[
+        Field::number("total-debt", "total debt"),
+        Field::number("shareholders-equity", "shareholders' equity").non_zero(),
+    ],
+    checks: &[],
+    run: run_debt_to_equity_ratio,
+    report: None,
+};
+
+fn run_debt_to_equity_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let dte = d_t_e(inputs.num("total-debt")?, inputs.num("shareholders-equity")?);
+    Ok(vec![Output::new("debt_to_equity_ratio", "Debt to Equity Ratio", dte)])
+}
+
+pub const DEBT_RATIO: Calculator = Calculator {
+    id: "debt_ratio",
+    function: Some("d_r"),
+    group: "ratio",
+    command: "debt",
+    title: "Debt Ratio",
+    fields: &[
+        Field::number("total-debt", "total debt"),
+        Field::number("total-assets", "total assets").non_zero(),
+    ],
+    checks: &[],
+    run: run_debt_ratio,
+    report: None,
+};
+
+fn run_debt_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let dr = d_r(inputs.num("total-debt")?, inputs.num("total-assets")?);
+    Ok(vec![Output::new("debt_ratio", "Debt Ratio", dr)])
+}
+
+pub const EBIT_INTEREST_COVERAGE: Calculator = Calculator {
+    id: "ebit_interest_coverage",
+    function: Some("ebit_i_c"),
+    group: "ratio",
+    command: "interest-coverage",
+    title: "EBIT Interest Coverage Ratio",
+    fields: &[
+        Field::number("ebit", "EBIT"),
+        Field::number("interest-expense", "interest expense").non_zero(),
+    ],
+    checks: &[],
+    run: run_ebit_interest_coverage,
+    report: None,
+};
+
+fn run_ebit_interest_coverage(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let ebit_ic = ebit_i_c(inputs.num("ebit")?, inputs.num("interest-expense")?);
+    Ok(vec![Output::new("ebit_interest_coverage", "EBIT Interest Coverage Ratio", ebit_ic)])
+}
+
+// Ratios - Activity
+
+pub const INVENTORY_TURNOVER: Calculator = Calculator {
+    id: "inventory_turnover",
+    function: Some("inv_t"),
+    group: "ratio",
+    command: "inventory-turnover",
+    title: "Inventory Turnover",
+    fields: &[
+        Field::number("cost-of-goods-sold", "cost of goods sold"),
+        Field::number("average-inventory", "average inventory").non_zero(),
+    ],
+    checks: &[],
+    run: run_inventory_turnover,
+    report: None,
+};
+
+fn run_inventory_turnover(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let turnover = inv_t(inputs.num("cost-of-goods-sold")?, inputs.num("average-inventory")?);
+    Ok(vec![Output::new("inventory_turnover", "Inventory Turnover", turnover)])
+}
+
+pub const RECEIVABLES_TURNOVER: Calculator = Calculator {
+    id: "receivables_turnover",
+    function: Some("rec_t"),
+    group: "ratio",
+    command: "receivables-turnover",
+    title: "Receivables Turnover",
+    fields: &[
+        Field::number("revenue", "revenue"),
+        Field::number("average-accounts-receivable", "average accounts receivable").non_zero(),
+    ],
+    checks: &[],
+    run: run_receivables_turnover,
+    report: None,
+};
+
+fn run_receivables_turnover(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let turnover = rec_t(inputs.num("revenue")?, inputs.num("average-accounts-receivable")?);
+    Ok(vec![Output::new("receivables_turnover", "Receivables Turnover", turnover)])
+}
+
+pub const ASSET_TURNOVER: Calculator = Calculator {
+    id: "asset_turnover",
+    function: Some("a_t"),
+    group: "ratio",
+    command: "asset-turnover",
+    title: "Asset Turnover",
+    fields: &[
+        Field::number("revenue", "revenue"),
+        Field::number("total-assets", "total assets").non_zero(),
+    ],
+    checks: &[],
+    run: run_asset_turnover,
+    report: None,
+};
+
+fn run_asset_turnover(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let turnover = a_t(inputs.num("revenue")?, inputs.num("total-assets")?);
+    Ok(vec![Output::new("asset_turnover", "Asset Turnover", turnover)])
+}
+
+// Ratios - Valuation
+
+pub const PRICE_TO_EARNINGS_RATIO: Calculator = Calculator {
+    id: "price_to_earnings_ratio",
+    function: Some("p_t_e"),
+    group: "ratio",
+    command: "pe",
+    title: "Price to Earnings (P/E) Ratio",
+    fields: &[
+        Field::number("share-price", "share price"),
+        Field::number("earnings-per-share", "earnings per share").non_zero(),
+    ],
+    checks: &[],
+    run: run_price_to_earnings_ratio,
+    report: None,
+};
+
+fn run_price_to_earnings_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let pte = p_t_e(inputs.num("share-price")?, inputs.num("earnings-per-share")?);
+    Ok(vec![Output::new("price_to_earnings_ratio", "Price to Earnings Ratio", pte)])
+}
+
+pub const PRICE_TO_BOOK_RATIO: Calculator = Calculator {
+    id: "price_to_book_ratio",
+    function: Some("p_t_b"),
+    group: "ratio",
+    command: "pb",
+    title: "Price to Book (P/B) Ratio",
+    fields: &[
+        Field::number("share-price", "share price"),
+        Field::number("book-value-per-share", "book value per share").non_zero(),
+    ],
+    checks: &[],
+    run: run_price_to_book_ratio,
+    report: None,
+};
+
+fn run_price_to_book_ratio(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let ptb = p_t_b(inputs.num("share-price")?, inputs.num("book-value-per-share")?);
+    Ok(vec![Output::new("price_to_book_ratio", "Price to Book Ratio", ptb)])
+}
+
+pub const DIVIDEND_YIELD: Calculator = Calculator {
+    id: "dividend_yield",
+    function: Some("div_y"),
+    group: "ratio",
+    command: "dividend-yield",
+    title: "Dividend Yield",
+    fields: &[
+        Field::number("annual-dividends-per-share", "annual dividends per share"),
+        Field::number("share-price", "share price").non_zero(),
+    ],
+    checks: &[],
+    run: run_dividend_yield,
+    report: None,
+};
+
+fn run_dividend_yield(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let div_yield = div_y(inputs.num("annual-dividends-per-share")?, inputs.num("share-price")?);
+    Ok(vec![Output::new("dividend_yield", "Dividend Yield", div_yield)])
+}
+
+// Time Value of Money
+
+pub const XNPV: Calculator = Calculator {
+    id: "xnpv",
+    function: None,
+    group: "tvm",
+    command: "xnpv",
+    title: "XNPV",
+    fields: &[
+        Field::cashflows("cashflow", "cash flow (AMOUNT,YYYY-MM-DD) or a CSV file of flows"),
+        Field::number("discount-rate", "discount rate (as a decimal, e.g., 0.05 for 5%)"),
+        DAY_COUNT,
+        BUSINESS_DAY,
+        HOLIDAY_CALENDAR,
+    ],
+    checks: &[],
+    run: run_xnpv,
+    report: Some(xnpv_report),
+};
+
+// Convention inputs shared by the dated cash flow calculators; the defaults match ifch's `xnpv`
+const DAY_COUNT: Field = Field::choice("day-count", "day count convention (act/act is ACT/ACT ISDA)", daycount::DAY_COUNTS).with_default("act/365f");
+const BUSINESS_DAY: Field = Field::choice("business-day", "business day adjustment", daycount::ADJUSTMENTS).with_default("none");
+const HOLIDAY_CALENDAR: Field =
+    Field::text("holiday-calendar", "holiday calendar file, one date per line (or 'none')", daycount::validate_calendar).with_default("none");
+
+fn conventions(inputs: &Inputs) -> Result<Conventions, String> {
+    Ok(Conventions {
+        day_count: DayCount::from_choice(inputs.choice("day-count")?),
+        adjustment: Adjustment::from_choice(inputs.choice("business-day")?),
+        calendar: daycount::load_calendar(inputs.text("holiday-calendar")?)?,
+    })
+}
+
+fn run_xnpv(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    xnpv_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+fn xnpv_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let cashflows = inputs.cashflows("cashflow")?;
+    let conventions = conventions(inputs)?;
+    let flows = irr::dated_flows(&cashflows, &conventions)?;
+    Ok(Evaluation::new(
+        vec![Output::new("xnpv", "XNPV", irr::xnpv(&flows, inputs.num("discount-rate")?))],
+        business_day_report(&cashflows, &conventions)?,
+    ))
+}
+
+// Lists the cash flows moved to a business day
+fn business_day_report(cashflows: &[(f64, &str)], conventions: &Conventions) -> Result<String, String> {
+    let adjusted = irr::adjusted_dates(cashflows, conventions)?;
+    let moved: Vec<String> = cashflows
+        .iter()
+        .zip(&adjusted)
+        .filter(|((_, date), adjusted)| adjusted.format("%Y-%m-%d").to_string() != *date)
+        .map(|((_, date), adjusted)| format!("  {} -> {}", date, adjusted))
+        .collect();
+    if moved.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(format!("\nMoved to business days:\n{}\n", moved.join("\n")))
+}
+
+pub const XIRR: Calculator = Calculator {
+    id: "xirr",
+    function: None,
+    group: "tvm",
+    command: "xirr",
+    title: "XIRR",
+    fields: &[
+        Field::cashflows("cashflow", "cash flow (AMOUNT,YYYY-MM-DD) or a CSV file of flows"),
+        DAY_COUNT,
+        BUSINESS_DAY,
+        HOLIDAY_CALENDAR,
+    ],
+    checks: &[],
+    run: run_xirr,
+    report: Some(xirr_report),
+};
+
+fn run_xirr(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    xirr_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+fn xirr_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let cashflows = inputs.cashflows("cashflow")?;
+    irr::check_irr_flows(&cashflows)?;
+    let conventions = conventions(inputs)?;
+    let flows = irr::dated_flows(&cashflows, &conventions)?;
+    let solution = irr::xirr(&flows).ok_or_else(|| "no IRR exists: the NPV does not cross zero between -99% and 1000% a year".to_string())?;
+    let outputs = vec![
+        Output::precise("xirr", "XIRR", solution.root.rate, 6),
+        Output::precise("iterations", "Iterations", solution.root.iterations as f64, 0),
+        Output::precise("residual", "NPV Residual", solution.root.residual, 10),
+    ];
+
+    let mut text = match solution.method {
+        irr::Method::Newton => String::from("\nSolved by Newton's method from 10%.\n"),
+        irr::Method::Brent => String::from("\nNewton's method did not converge; solved by Brent's method on a bracket of the NPV profile.\n"),
+    };
+    let amounts: Vec<f64> = cashflows.iter().map(|(amount, _)| *amount).collect();
+    let changes = irr::sign_changes(&amounts);
+    if changes > 1 {
+        text.push_str(&format!("Warning: the cash flows change sign {} times and may have several IRRs.\n", changes));
+    }
+    if solution.roots.len() > 1 {
+        let rates: Vec<String> = solution.roots.iter().map(|root| format!("{:.4}%", 100.0 * root.rate)).collect();
+        text.push_str(&format!("The NPV is zero at {} rates: {}\n", solution.roots.len(), rates.join(", ")));
+    }
+    text.push_str(&business_day_report(&cashflows, &conventions)?);
+    Ok(Evaluation::new(outputs, text))
+}
+
+pub const PERIODIC_CASHFLOWS: Calculator = Calculator {
+    id: "periodic_cashflows",
+    function: None,
+    group: "tvm",
+    command: "cashflow-analysis",
+    title: "Periodic Cash Flow Analysis (NPV, IRR, MIRR, Payback)",
+    fields: &[
+        Field::numbers("cashflows", "periodic cash flows CF0; CF1; ... (CF0 at time 0)"),
+        Field::number("discount-rate", "discount rate per period (as a decimal)"),
+        Field::number("finance-rate", "finance rate for negative flows (MIRR)"),
+        Field::number("reinvestment-rate", "reinvestment rate for positive flows (MIRR)"),
+    ],
+    checks: &[],
+    run: run_periodic_cashflows,
+    report: Some(periodic_cashflows_report),
+};
+
+// Outputs that do not exist for the flows (no IRR, never paid back, ...) are left out
+fn run_periodic_cashflows(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    periodic_cashflows_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+fn periodic_cashflows_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let flows = inputs.numbers("cashflows")?;
+    let rate = inputs.num("discount-rate")?;
+    if rate <= -1.0 {
+        return Err("discount-rate must be greater than -1".to_string());
+    }
+    let roots = irr::find_roots(|r| irr::npv(flows, r));
+    let mut outputs = vec![Output::new("npv", "NPV", irr::npv(flows, rate))];
+    if let Some(root) = roots.first() {
+        outputs.push(Output::precise("irr", "IRR", root.rate, 6));
+    }
+    if let Some(mirr) = irr::mirr(flows, inputs.num("finance-rate")?, inputs.num("reinvestment-rate")?) {
+        outputs.push(Output::precise("mirr", "MIRR", mirr, 6));
+    }
+    if let Some(periods) = irr::payback(flows) {
+        outputs.push(Output::precise("payback", "Payback Period", periods, 4));
+    }
+    if let Some(periods) = irr::discounted_payback(flows, rate) {
+        outputs.push(Output::precise("discounted_payback", "Discounted Payback Period", periods, 4));
+    }
+    if let Some(index) = irr::profitability_index(flows, rate) {
+        outputs.push(Output::precise("profitability_index", "Profitability Index", index, 4));
+    }
+
+    let changes = irr::sign_changes(flows);
+    let mut text = String::new();
+    if changes > 1 {
+        text.push_str(&format!(
+            "\nWarning: the cash flows change sign {} times, so they are non-conventional and may have \
+several IRRs or none; prefer NPV or MIRR.\n",
+            changes
+        ));
+    }
+    match roots.len() {
+        0 => text.push_str("No IRR exists between -99% and 1000% per period.\n"),
+        1 => {}
+        _ => {
+            let rates: Vec<String> = roots.iter().map(|root| format!("{:.4}%", 100.0 * root.rate)).collect();
+            text.push_str(&format!("The NPV is zero at {} IRRs: {}\n", roots.len(), rates.join(", ")));
+        }
+    }
+    if irr::payback(flows).is_none() {
+        text.push_str("The cumulative cash flows never turn non-negative, so there is no payback period.\n");
+    }
+    Ok(Evaluation::new(outputs, text))
+}
+
+// Five-key TVM: one calculator per unknown, sharing the inputs below. PV, PMT and FV follow the
+// calculator sign convention (money paid out is negative).
+
+const TVM_PV: Field = Field::number("present-value", "present value (PV; money paid out is negative)");
+const TVM_FV: Field = Field::number("future-value", "future value (FV)");
+const TVM_PMT: Field = Field::number("payment", "payment per period (PMT, the first one when growing)");
+const TVM_N: Field = Field::number("periods", "number of payment periods (N)").positive();
+const TVM_RATE: Field = Field::number("rate", "nominal annual interest rate (as a decimal)");
+const TVM_GROWTH: Field = Field::number("growth-rate", "payment growth rate per period").with_default("0");
+
+macro_rules! tvm_fields {
+    ($($field:expr),* $(,)?) => {
+        &[
+            $($field,)*
+            TVM_GROWTH,
+            Field::choice("timing", "payment timing", &["end", "begin"]).with_default("end"),
+            Field::number("payments-per-year", "payments per year").positive().with_default("1"),
+            Field::number("compounding-per-year", "compounding periods per year").positive().with_default("1"),
+        ]
+    };
+}
+
+// Rate, growth and timing per payment period
+fn tvm_terms(inputs: &Inputs) -> Result<tvm::Terms, String> {
+    Ok(tvm::Terms {
+        rate: tvm_periodic_rate(inputs, inputs.num("rate")?)?,
+        growth: inputs.num("growth-rate")?,
+        timing: tvm::Timing::from_choice(inputs.choice("timing")?),
+    })
+}
+
+fn tvm_periodic_rate(inputs: &Inputs, nominal: f64) -> Result<f64, String> {
+    tvm::periodic_rate(nominal, inputs.num("compounding-per-year")?, inputs.num("payments-per-year")?)
+}
+
+pub const TVM_PRESENT_VALUE: Calculator = Calculator {
+    id: "tvm_pv",
+    function: None,
+    group: "tvm",
+    command: "pv",
+    title: "TVM - Solve for Present Value",
+    fields: tvm_fields![TVM_FV, TVM_PMT, TVM_N, TVM_RATE],
+    checks: &[],
+    run: run_tvm_present_value,
+    report: None,
+};
+
+fn run_tvm_present_value(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let pv = tvm::present_value(inputs.num("payment")?, inputs.num("future-value")?, inputs.num("periods")?, tvm_terms(inputs)?);
+    Ok(vec![Output::new("present_value", "Present Value", pv)])
+}
+
+pub const TVM_FUTURE_VALUE: Calculator = Calculator {
+    id: "tvm_fv",
+    function: None,
+    group: "tvm",
+    command: "fv",
+    title: "TVM - Solve for Future Value",
+    fields: tvm_fields![TVM_PV, TVM_PMT, TVM_N, TVM_RATE],
+    checks: &[],
+    run: run_tvm_future_value,
+    report: None,
+};
+
+fn run_tvm_future_value(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let fv = tvm::future_value(inputs.num("present-value")?, inputs.num("payment")?, inputs.num("periods")?, tvm_terms(inputs)?);
+    Ok(vec![Output::new("future_value", "Future Value", fv)])
+}
+
+pub const TVM_PAYMENT: Calculator = Calculator {
+    id: "tvm_pmt",
+    function: None,
+    group: "tvm",
+    command: "pmt",
+    title: "TVM - Solve for Payment",
+    fields: tvm_fields![TVM_PV, TVM_FV, TVM_N, TVM_RATE],
+    checks: &[],
+    run: run_tvm_payment,
+    report: None,
+};
+
+fn run_tvm_payment(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let pmt = tvm::payment(inputs.num("present-value")?, inputs.num("future-value")?, inputs.num("periods")?, tvm_terms(inputs)?)?;
+    Ok(vec![Output::new("payment", "Payment", pmt)])
+}
+
+pub const TVM_PERIODS: Calculator = Calculator {
+    id: "tvm_n",
+    function: None,
+    group: "tvm",
+    command: "periods",
+    title: "TVM - Solve for Number of Periods",
+    fields: tvm_fields![TVM_PV, TVM_FV, TVM_PMT, TVM_RATE],
+    checks: &[],
+    run: run_tvm_periods,
+    report: None,
+};
+
+fn run_tvm_periods(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let n = tvm::periods(inputs.num("present-value")?, inputs.num("payment")?, inputs.num("future-value")?, tvm_terms(inputs)?)?;
+    Ok(vec![
+        Output::precise("periods", "Number of Periods", n, 4),
+        Output::precise("years", "Years", n / inputs.num("payments-per-year")?, 4),
+    ])
+}
+
+pub const TVM_RATE_SOLVER: Calculator = Calculator {
+    id: "tvm_rate",
+    function: None,
+    group: "tvm",
+    command: "rate",
+    title: "TVM - Solve for Interest Rate",
+    fields: tvm_fields![TVM_PV, TVM_FV, TVM_PMT, TVM_N],
+    checks: &[],
+    run: run_tvm_rate,
+    report: None,
+};
+
+fn run_tvm_rate(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let timing = tvm::Timing::from_choice(inputs.choice("timing")?);
+    let periodic = tvm::rate(
+        inputs.num("present-value")?,
+        inputs.num("payment")?,
+        inputs.num("future-value")?,
+        inputs.num("periods")?,
+        inputs.num("growth-rate")?,
+        timing,
+    )?;
+    let payments = inputs.num("payments-per-year")?;
+    Ok(vec![
+        Output::precise("rate", "Nominal Annual Rate", tvm::nominal_rate(periodic, inputs.num("compounding-per-year")?, payments), 6),
+        Output::precise("periodic_rate", "Rate per Period", periodic, 6),
+        Output::precise("effective_annual_rate", "Effective Annual Rate", tvm::effective_annual_rate(periodic, payments), 6),
+    ])
+}
+
+pub const PERPETUITY: Calculator = Calculator {
+    id: "perpetuity",
+    function: None,
+    group: "tvm",
+    command: "perpetuity",
+    title: "Growing Perpetuity",
+    fields: tvm_fields![TVM_PMT, TVM_RATE],
+    checks: &[],
+    run: run_perpetuity,
+    report: None,
+};
+
+fn run_perpetuity(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let pv = tvm::perpetuity(inputs.num("payment")?, tvm_terms(inputs)?)?;
+    Ok(vec![Output::new("present_value", "Present Value", pv)])
+}
+
+pub const LOAN_AMORTIZATION: Calculator = Calculator {
+    id: "loan_amortization",
+    function: None,
+    group: "tvm",
+    command: "amortization",
+    title: "Loan Amortization Schedule",
+    fields: &[
+        Field::choice("loan-type", "loan type", amortization::LOAN_TYPES),
+        Field::number("principal", "loan amount").positive(),
+        Field::number("rate", "annual interest rate (as a decimal)"),
+        Field::count("payments", "number of payments"),
+        Field::number("payments-per-year", "payments per year").positive().with_default("12"),
+        Field::count("amortization-periods", "amortization periods for a balloon loan, no fewer than the payments (0 = the number of payments)").with_default("0"),
+        Field::number("extra-payment", "extra principal paid every period").with_default("0"),
+        Field::pairs("prepayments", "lump-sum prepayments", "PERIOD,AMOUNT"),
+        Field::number("fees", "upfront fees (for the effective APR)").with_default("0"),
+        Field::text("export-file", "CSV file to write the schedule to (or 'none')", amortization::validate_export_file).with_default("none"),
+    ],
+    checks: &[],
+    run: run_loan_amortization,
+    report: Some(loan_amortization_report),
+};
+
+fn loan(inputs: &Inputs) -> Result<amortization::Loan, String> {
+    let payments = inputs.count("payments")?;
+    if payments == 0 || payments > amortization::MAX_PAYMENTS {
+        return Err(format!("payments must be between 1 and {}", amortization::MAX_PAYMENTS));
+    }
+    let amortization_periods = inputs.count("amortization-periods")?;
+    if amortization_periods > amortization::MAX_PAYMENTS {
+        return Err(format!("amortization-periods must be at most {}", amortization::MAX_PAYMENTS));
+    }
+    // Amortizing faster than the loan runs would pay it off early rather than leave a balloon
+    if inputs.choice("loan-type")? == "balloon" && amortization_periods > 0 && amortization_periods < payments {
+        return Err(format!("amortization-periods must be 0 or at least the {} payments", payments));
+    }
+    if inputs.num("extra-payment")? < 0.0 {
+        return Err("extra-payment must not be negative".to_string());
+    }
+    let prepayments = inputs.pairs("prepayments")?.to_vec();
+    if let Some((period, amount)) = prepayments.iter().find(|(period, amount)| period.fract() != 0.0 || *period < 1.0 || *period > payments as f64 || *amount <= 0.0) {
+        return Err(format!("prepayment {},{} needs a whole period between 1 and {} and a positive amount", period, amount, payments));
+    }
+    Ok(amortization::Loan {
+        kind: inputs.choice("loan-type")?,
+        principal: inputs.num("principal")?,
+        rate: inputs.num("rate")? / inputs.num("payments-per-year")?,
+        payments,
+        amortization_periods,
+        extra_payment: inputs.num("extra-payment")?,
+        prepayments,
+    })
+}
+
+// Only batch rows and JSON runs get here, and they do not write files, so an export would be lost
+fn run_loan_amortization(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    if !inputs.text("export-file")?.trim().eq_ignore_ascii_case("none") {
+        return Err("export-file is only written with text output or from the menu; set it to 'none' for batch rows and JSON output".to_string());
+    }
+    loan_amortization_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+fn loan_amortization_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let loan = loan(inputs)?;
+    let fees = inputs.num("fees")?;
+    if fees < 0.0 || fees >= loan.principal {
+        return Err("fees must be at least 0 and less than the loan amount".to_string());
+    }
+    let schedule = amortization::schedule(&loan);
+
+    let mut outputs = vec![
+        Output::new("payment", "Scheduled Payment", schedule.scheduled_payment),
+        Output::new("final_balance", "Final Balance", schedule.final_balance),
+        Output::new("total_interest", "Total Interest", schedule.total_interest()),
+        Output::new("total_paid", "Total Paid", schedule.total_paid()),
+        Output::precise("periods", "Payments Made", schedule.rows.len() as f64, 0),
+    ];
+    let payments_per_year = inputs.num("payments-per-year")?;
+    if let Some(rate) = amortization::effective_rate(&schedule, loan.principal, fees) {
+        outputs.push(Output::precise("effective_apr", "Effective APR", rate * payments_per_year, 6));
+        outputs.push(Output::precise("effective_annual_rate", "Effective Annual Rate", tvm::effective_annual_rate(rate, payments_per_year), 6));
+    }
+    if loan.extra_payment > 0.0 || !loan.prepayments.is_empty() {
+        outputs.push(Output::new("interest_saved", "Interest Saved by Prepaying", schedule.interest_without_prepayments - schedule.total_interest()));
+    }
+
+    let mut evaluation = Evaluation::new(outputs, amortization::table(&schedule, 60));
+    let export_file = inputs.text("export-file")?.trim();
+    if !export_file.eq_ignore_ascii_case("none") {
+        evaluation.files.push((PathBuf::from(export_file), amortization::csv(&schedule)?));
+    }
+    Ok(evaluation)
+}
+
+pub const CASHFLOW_DURATION: Calculator = Calculator {
+    id: "cashflow_duration",
+    function: None,
+    group: "tvm",
+    command: "duration",
+    title: "Cash Flow Duration, Convexity and DV01",
+    fields: &[
+        Field::cashflows("cashflow", "cash flow (AMOUNT,YYYY-MM-DD) or a CSV file of flows"),
+        Field::number("discount-rate", "discount rate (annual, as a decimal)"),
+        Field::choice("compounding", "compounding of the discount rate and curve", duration::COMPOUNDINGS).with_default("annual"),
+        ZERO_CURVE,
+        Field::text("valuation-date", "valuation date (YYYY-MM-DD, or 'first' for the first flow)", validate_valuation_date).with_default("first"),
+        RATE_SHOCK,
+        DAY_COUNT,
+        BUSINESS_DAY,
+        HOLIDAY_CALENDAR,
+    ],
+    checks: &[],
+    run: run_cashflow_duration,
+    report: Some(cashflow_duration_report),
+};
+
+// Inputs shared by the duration calculators
+const ZERO_CURVE: Field = Field::pairs("curve", "zero curve, used instead of the flat rate when given", "YEARS,RATE");
+const RATE_SHOCK: Field =
+    Field::number("shock", "rate shock for effective duration and convexity (as a decimal)").positive().with_default("0.001");
+
+fn validate_valuation_date(text: &str) -> Result<(), String> {
+    if text.trim().eq_ignore_ascii_case("first") {
+        return Ok(());
+    }
+    bond::validate_date(text)
+}
+
+fn zero_curve(inputs: &Inputs, flat: f64) -> Result<duration::Curve, String> {
+    duration::Curve::new(flat, inputs.pairs("curve")?.to_vec())
+}
+
+// The flows as (years from the valuation date, amount)
+fn cashflow_years(inputs: &Inputs) -> Result<Vec<(f64, f64)>, String> {
+    let conventions = conventions(inputs)?;
+    let cashflows = inputs.cashflows("cashflow")?;
+    let dates = irr::adjusted_dates(&cashflows, &conventions)?;
+    let valuation = match inputs.text("valuation-date")? {
+        text if text.eq_ignore_ascii_case("first") => dates.iter().min().copied().ok_or("no cash flows to analyse")?,
+        text => bond::parse_date(text)?,
+    };
+    Ok(cashflows.iter().zip(&dates).map(|((amount, _), date)| (conventions.day_count.year_fraction(valuation, *date), *amount)).collect())
+}
+
+fn cashflow_analytics(inputs: &Inputs) -> Result<duration::Analytics, String> {
+    let curve = zero_curve(inputs, inputs.num("discount-rate")?)?;
+    let compounding = duration::Compounding::from_choice(inputs.choice("compounding")?);
+    duration::analyse(&cashflow_years(inputs)?, &curve, compounding, inputs.num("shock")?)
+}
+
+fn run_cashflow_duration(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    cashflow_duration_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+// Durations in years and convexities in years squared; DV01 and PV01 are in the units of the value
+fn duration_outputs(analytics: &duration::Analytics, value: Output) -> Vec<Output> {
+    let mut outputs = vec![
+        Output::precise("macaulay_duration", "Macaulay Duration", analytics.macaulay, 4),
+        Output::precise("modified_duration", "Modified Duration", analytics.modified, 4),
+        Output::precise("convexity", "Convexity", analytics.convexity, 4),
+        Output::precise("dv01", "DV01", analytics.dv01, 6),
+        Output::precise("effective_duration", "Effective Duration", analytics.effective_duration, 4),
+        Output::precise("effective_convexity", "Effective Convexity", analytics.effective_convexity, 4),
+        Output::precise("pv01", "PV01", analytics.pv01, 6),
+        value,
+    ];
+    outputs.extend(duration::KEY_RATES.iter().zip(&analytics.key_rates).map(|((_, name, label), krd)| Output::precise(name, label, *krd, 4)));
+    outputs
+}
+
+fn cashflow_duration_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let analytics = cashflow_analytics(inputs)?;
+    let discounting = if inputs.pairs("curve")?.is_empty() { "the flat discount rate" } else { "the zero curve" };
+    let mut text = duration_report(&analytics, discounting);
+    text.push_str(&business_day_report(&inputs.cashflows("cashflow")?, &conventions(inputs)?)?);
+    Ok(Evaluation::new(
+        duration_outputs(&analytics, Output::new("present_value", "Present Value", analytics.present_value)),
+        text,
+    ))
+}
+
+// How the flows were discounted and how the key rate durations add up
+fn duration_report(analytics: &duration::Analytics, discounting: &str) -> String {
+    let total: f64 = analytics.key_rates.iter().sum();
+    format!(
+        "\nDiscounted on {}. Key rate durations sum to {:.4} against an effective duration of {:.4}.\n",
+        discounting, total, analytics.effective_duration
+    )
+}
+
+// Build Ups
+
+pub const FCFF_NI: Calculator = Calculator {
+    id: "fcff_ni",
+    function: Some("fcff_ni"),
+    group: "build-up",
+    command: "fcff-ni",
+    title: "FCFF using Net Income",
+    fields: &[
+        Field::number("net-income", "net income"),
+        Field::number("non-cash-charges", "non-cash charges"),
+        Field::number("interest", "interest"),
+        Field::number("tax-rate", "tax rate").fraction(),
+        Field::number("capex", "capital expenditures (CapEx)"),
+        Field::number("change-in-working-capital", "change in working capital"),
+    ],
+    checks: &[],
+    run: run_fcff_ni,
+    report: None,
+};
+
+fn run_fcff_ni(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let fcff = fcff_ni(
+        inputs.num("net-income")?,
+        inputs.num("non-cash-charges")?,
+        inputs.num("interest")?,
+        inputs.num("tax-rate")?,
+        inputs.num("capex")?,
+        inputs.num("change-in-working-capital")?,
+    );
+    Ok(vec![Output::new("fcff", "FCFF using Net Income", fcff)])
+}
+
+pub const FCFF_CFO: Calculator = Calculator {
+    id: "fcff_cfo",
+    function: Some("fcff_cfo"),
+    group: "build-up",
+    command: "fcff-cfo",
+    title: "FCFF using CFO",
+    fields: &[
+        Field::number("cfo", "cash flow from operations (CFO)"),
+        Field::number("interest-expense", "interest expense"),
+        Field::number("tax-rate", "tax rate").fraction(),
+        Field::number("capex", "capital expenditures (CapEx)"),
+    ],
+    checks: &[],
+    run: run_fcff_cfo,
+    report: None,
+};
+
+fn run_fcff_cfo(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let fcff = fcff_cfo(
+        inputs.num("cfo")?,
+        inputs.num("interest-expense")?,
+        inputs.num("tax-rate")?,
+        inputs.num("capex")?,
+    );
+    Ok(vec![Output::new("fcff", "FCFF using CFO", fcff)])
+}
+
+pub const FCFF_EBIT: Calculator = Calculator {
+    id: "fcff_ebit",
+    function: Some("fcff_ebit"),
+    group: "build-up",
+    command: "fcff-ebit",
+    title: "FCFF using EBIT",
+    fields: &[
+        Field::number("ebit", "EBIT"),
+        Field::number("tax-rate", "tax rate").fraction(),
+        Field::number("depreciation", "depreciation"),
+        Field::number("capex", "capital expenditures (CapEx)"),
+        Field::number("change-in-working-capital", "change in working capital"),
+    ],
+    checks: &[],
+    run: run_fcff_ebit,
+    report: None,
+};
+
+fn run_fcff_ebit(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let fcff = fcff_ebit(
+        inputs.num("ebit")?,
+        inputs.num("tax-rate")?,
+        inputs.num("depreciation")?,
+        inputs.num("capex")?,
+        inputs.num("change-in-working-capital")?,
+    );
+    Ok(vec![Output::new("fcff", "FCFF using EBIT", fcff)])
+}
+
+pub const FCFF_EBITDA: Calculator = Calculator {
+    id: "fcff_ebitda",
+    function: Some("fcff_ebitda"),
+    group: "build-up",
+    command: "fcff-ebitda",
+    title: "FCFF using EBITDA",
+    fields: &[
+        Field::number("ebitda", "EBITDA"),
+        Field::number("tax-rate", "tax rate").fraction(),
+        Field::number("depreciation", "depreciation"),
+        Field::number("capex", "capital expenditures (CapEx)"),
+        Field::number("change-in-working-capital", "change in working capital"),
+    ],
+    checks: &[],
+    run: run_fcff_ebitda,
+    report: None,
+};
+
+fn run_fcff_ebitda(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let fcff = fcff_ebitda(
+        inputs.num("ebitda")?,
+        inputs.num("tax-rate")?,
+        inputs.num("depreciation")?,
+        inputs.num("capex")?,
+        inputs.num("change-in-working-capital")?,
+    );
+    Ok(vec![Output::new("fcff", "FCFF using EBITDA", fcff)])
+}
+
+// WACC
+
+pub const WACC_COE: Calculator = Calculator {
+    id: "wacc_coe",
+    function: Some("wacc_coe"),
+    group: "wacc",
+    command: "wacc-coe",
+    title: "WACC using Cost of Equity",
+    fields: &[
+        Field::number("coe", "cost of equity (COE)"),
+        Field::number("we", "weight of equity (WE)").fraction(),
+        Field::number("tax-rate", "tax rate").fraction(),
+        Field::number("cod", "cost of debt (COD)"),
+        Field::number("wd", "weight of debt (WD)").fraction(),
+        Field::number("cop", "cost of preferred stock (COP)"),
+        Field::number("wp", "weight of preferred stock (WP)").fraction(),
+    ],
+    checks: &[Check::SumsToOne(&["we", "wd", "wp"])],
+    run: run_wacc_coe,
+    report: None,
+};
+
+fn run_wacc_coe(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let wacc = wacc_coe(
+        inputs.num("coe")?,
+        inputs.num("we")?,
+        inputs.num("tax-rate")?,
+        inputs.num("cod")?,
+        inputs.num("wd")?,
+        inputs.num("cop")?,
+        inputs.num("wp")?,
+    );
+    Ok(vec![Output::new("wacc", "WACC using Cost of Equity", wacc)])
+}
+
+pub const COE: Calculator = Calculator {
+    id: "coe",
+    function: Some("coe"),
+    group: "wacc",
+    command: "coe",
+    title: "Cost of Equity (COE)",
+    fields: &[
+        Field::number("rfr", "risk-free rate (RFR)"),
+        Field::number("equity-beta", "equity beta"),
+        Field::number("mrp", "market risk premium (MRP)"),
+    ],
+    checks: &[],
+    run: run_coe,
+    report: None,
+};
+
+fn run_coe(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let coe_value = coe(inputs.num("rfr")?, inputs.num("equity-beta")?, inputs.num("mrp")?);
+    Ok(vec![Output::new("coe", "Cost of Equity", coe_value)])
+}
+
+pub const WACC_BETA: Calculator = Calculator {
+    id: "wacc_beta",
+    function: Some("wacc_beta"),
+    group: "wacc",
+    command: "wacc-beta",
+    title: "WACC using Equity Beta",
+    fields: &[
+        Field::number("equity-beta", "equity beta"),
+        Field::number("rfr", "risk-free rate (RFR)"),
+        Field::number("mrp", "market risk premium (MRP)"),
+        Field::number("we", "weight of equity (WE)").fraction(),
+        Field::number("tax-rate", "tax rate").fraction(),
+        Field::number("cod", "cost of debt (COD)"),
+        Field::number("wd", "weight of debt (WD)").fraction(),
+        Field::number("cop", "cost of preferred stock (COP)"),
+        Field::number("wp", "weight of preferred stock (WP)").fraction(),
+    ],
+    checks: &[Check::SumsToOne(&["we", "wd", "wp"])],
+    run: run_wacc_beta,
+    report: None,
+};
+
+fn run_wacc_beta(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let wacc = wacc_beta(
+        inputs.num("equity-beta")?,
+        inputs.num("rfr")?,
+        inputs.num("mrp")?,
+        inputs.num("we")?,
+        inputs.num("tax-rate")?,
+        inputs.num("cod")?,
+        inputs.num("wd")?,
+        inputs.num("cop")?,
+        inputs.num("wp")?,
+    );
+    Ok(vec![Output::new("wacc", "WACC using Equity Beta", wacc)])
+}
+
+pub const MRP: Calculator = Calculator {
+    id: "mrp",
+    function: Some("mrp"),
+    group: "wacc",
+    command: "mrp",
+    title: "Market Risk Premium (MRP)",
+    fields: &[
+        Field::number("equity-market-return", "equity market return"),
+        Field::number("rfr", "risk-free rate (RFR)"),
+    ],
+    checks: &[],
+    run: run_mrp,
+    report: None,
+};
+
+fn run_mrp(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let mrp_value = mrp(inputs.num("equity-market-return")?, inputs.num("rfr")?);
+    Ok(vec![Output::new("mrp", "Market Risk Premium", mrp_value)])
+}
+
+pub const EQUITY_BETA: Calculator = Calculator {
+    id: "equity_beta",
+    function: Some("equity_beta"),
+    group: "wacc",
+    command: "equity-beta",
+    title: "Equity Beta",
+    fields: &[
+        Field::number("equity", "equity").non_zero(),
+        Field::number("debt", "debt"),
+        Field::number("asset-beta", "asset beta"),
+        Field::number("tax-rate", "tax rate").fraction(),
+    ],
+    checks: &[],
+    run: run_equity_beta,
+    report: None,
+};
+
+fn run_equity_beta(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let beta = equity_beta(
+        inputs.num("equity")?,
+        inputs.num("debt")?,
+        inputs.num("asset-beta")?,
+        inputs.num("tax-rate")?,
+    );
+    Ok(vec![Output::new("equity_beta", "Equity Beta", beta)])
+}
+
+pub const ASSET_BETA: Calculator = Calculator {
+    id: "asset_beta",
+    function: Some("asset_beta"),
+    group: "wacc",
+    command: "asset-beta",
+    title: "Asset Beta",
+    fields: &[
+        Field::number("equity", "equity").non_zero(),
+        Field::number("debt", "debt"),
+        Field::number("equity-beta", "equity beta"),
+        Field::number("tax-rate", "tax rate").fraction(),
+    ],
+    checks: &[],
+    run: run_asset_beta,
+    report: None,
+};
+
+fn run_asset_beta(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let beta = asset_beta(
+        inputs.num("equity")?,
+        inputs.num("debt")?,
+        inputs.num("equity-beta")?,
+        inputs.num("tax-rate")?,
+    );
+    Ok(vec![Output::new("asset_beta", "Asset Beta", beta)])
+}
+
+// Valuation Models
+
+pub const GGM_P1: Calculator = Calculator {
+    id: "ggm_p1",
+    function: Some("ggm_p1"),
+    group: "valuation",
+    command: "ggm-p1",
+    title: "Gordon Growth Model - One Phase",
+    fields: &[
+        Field::number("cashflow-0", "initial cash flow (Cashflow_0)"),
+        Field::number("required-return", "required rate of return (as a decimal, e.g., 0.05 for 5%)"),
+        Field::number("growth-rate", "growth rate (as a decimal, e.g., 0.02 for 2%)"),
+    ],
+    checks: &[],
+    run: run_ggm_p1,
+    report: None,
+};
+
+fn run_ggm_p1(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    match ggm_p1(inputs.num("cashflow-0")?, inputs.num("required-return")?, inputs.num("growth-rate")?) {
+        Some(value) => Ok(vec![Output::new("value", "GGM One Phase Valuation", value)]),
+        None => Err("Invalid input: Required rate of return must be greater than growth rate.".to_string()),
+    }
+}
+
+pub const GGM_P2: Calculator = Calculator {
+    id: "ggm_p2",
+    function: Some("ggm_p2"),
+    group: "valuation",
+    command: "ggm-p2",
+    title: "Gordon Growth Model - Two Phase",
+    fields: &[
+        Field::number("cashflow-0", "initial cash flow (Cashflow_0)"),
+        Field::number("required-return", "required rate of return (as a decimal, e.g., 0.05 for 5%)"),
+        Field::number("growth-rate-1", "first phase growth rate (as a decimal, e.g., 0.03 for 3%)"),
+        Field::number("growth-rate-2", "second phase growth rate (as a decimal, e.g., 0.02 for 2%)"),
+        Field::count("periods", "number of periods for first phase"),
+    ],
+    checks: &[],
+    run: run_ggm_p2,
+    report: None,
+};
+
+fn run_ggm_p2(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    match ggm_p2(
+        inputs.num("cashflow-0")?,
+        inputs.num("required-return")?,
+        inputs.num("growth-rate-1")?,
+        inputs.num("growth-rate-2")?,
+        inputs.count("periods")?,
+    ) {
+        Some(value) => Ok(vec![Output::new("value", "GGM Two Phase Valuation", value)]),
+        None => Err("Invalid input: Required rate of return must be greater than second phase growth rate.".to_string()),
+    }
+}
+
+// Options
+
+pub const BSM: Calculator = Calculator {
+    id: "bsm",
+    function: Some("bsm"),
+    group: "option",
+    command: "bsm",
+    title: "Black-Scholes-Merton",
+    fields: &[
+        Field::number("stock-price", "stock price").positive(),
+        Field::number("strike-price", "strike price").positive(),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free rate"),
+        Field::number("volatility", "volatility").positive(),
+        Field::number("dividend-yield", "dividend yield"),
+    ],
+    checks: &[],
+    run: run_bsm,
+    report: None,
+};
+
+fn run_bsm(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let (call_price, put_price, nd1, nd2) = bsm(
+        inputs.num("stock-price")?,
+        inputs.num("strike-price")?,
+        inputs.num("time-to-expiration")?,
+        inputs.num("risk-free-rate")?,
+        inputs.num("volatility")?,
+        inputs.num("dividend-yield")?,
+    );
+    let (call, put) = options::bsm_greeks(
+        inputs.num("stock-price")?,
+        inputs.num("strike-price")?,
+        inputs.num("time-to-expiration")?,
+        inputs.num("risk-free-rate")?,
+        inputs.num("volatility")?,
+        inputs.num("dividend-yield")?,
+    );
+    let mut outputs = vec![
+        Output::new("call_price", "Call Price", call_price),
+        Output::new("put_price", "Put Price", put_price),
+        Output::new("nd1", "N(d1)", nd1),
+        Output::new("nd2", "N(d2)", nd2),
+    ];
+    outputs.extend(greek_outputs(&call, &put, 4));
+    outputs.push(Output::precise("call_dividend_rho", "Call Dividend Rho", call.dividend_rho, 4));
+    outputs.push(Output::precise("put_dividend_rho", "Put Dividend Rho", put.dividend_rho, 4));
+    Ok(outputs)
+}
+
+pub const OPTION_KINDS: &[&str] = &["call", "put"];
+
+pub const BSM_IMPLIED_VOL: Calculator = Calculator {
+    id: "bsm_implied_vol",
+    function: None,
+    group: "option",
+    command: "implied-vol",
+    title: "Black-Scholes-Merton Implied Volatility",
+    fields: &[
+        Field::choice("option-type", "option type", OPTION_KINDS),
+        Field::number("market-price", "market option price").positive(),
+        Field::number("stock-price", "stock price").positive(),
+        Field::number("strike-price", "strike price").positive(),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free rate"),
+        Field::number("dividend-yield", "dividend yield"),
+    ],
+    checks: &[],
+    run: run_bsm_implied_vol,
+    report: None,
+};
+
+fn run_bsm_implied_vol(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let solution = options::bsm_implied_vol(
+        OptionKind::from_choice(inputs.choice("option-type")?),
+        inputs.num("market-price")?,
+        inputs.num("stock-price")?,
+        inputs.num("strike-price")?,
+        inputs.num("time-to-expiration")?,
+        inputs.num("risk-free-rate")?,
+        inputs.num("dividend-yield")?,
+    )?;
+    Ok(implied_vol_outputs(&solution))
+}
+
+fn implied_vol_outputs(solution: &ImpliedVol) -> Vec<Output> {
+    vec![
+        Output::precise("implied_volatility", "Implied Volatility", solution.volatility, 6),
+        Output::precise("iterations", "Iterations", solution.iterations as f64, 0),
+        Output::precise("residual", "Price Residual", solution.residual, 10),
+    ]
+}
+
+const TREE_FIELDS: &[Field] = &[
+    Field::choice("option-type", "option type", OPTION_KINDS),
+    Field::number("stock-price", "stock price").positive(),
+    Field::number("strike-price", "strike price").positive(),
+    Field::number("time-to-expiration", "time to expiration (years)").positive(),
+    Field::number("risk-free-rate", "risk-free rate"),
+    Field::number("volatility", "volatility").positive(),
+    Field::number("dividend-yield", "continuous dividend yield"),
+    Field::pairs("dividends", "discrete dividends", "TIME,AMOUNT"),
+    Field::count("steps", "number of steps"),
+];
+
+pub const BINOMIAL_TREE: Calculator = Calculator {
+    id: "binomial_tree",
+    function: None,
+    group: "option",
+    command: "binomial",
+    title: "American Option - Binomial Tree (CRR)",
+    fields: TREE_FIELDS,
+    checks: &[],
+    run: run_binomial_tree,
+    report: None,
+};
+
+fn run_binomial_tree(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    run_tree(inputs, Tree::Binomial)
+}
+
+pub const TRINOMIAL_TREE: Calculator = Calculator {
+    id: "trinomial_tree",
+    function: None,
+    group: "option",
+    command: "trinomial",
+    title: "American Option - Trinomial Tree",
+    fields: TREE_FIELDS,
+    checks: &[],
+    run: run_trinomial_tree,
+    report: None,
+};
+
+fn run_trinomial_tree(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    run_tree(inputs, Tree::Trinomial)
+}
+
+fn vanilla(inputs: &Inputs) -> Result<Vanilla, String> {
+    Ok(Vanilla {
+        kind: OptionKind::from_choice(inputs.choice("option-type")?),
+        spot: inputs.num("stock-price")?,
+        strike: inputs.num("strike-price")?,
+        time: inputs.num("time-to-expiration")?,
+        rate: inputs.num("risk-free-rate")?,
+        volatility: inputs.num("volatility")?,
+        dividend_yield: inputs.num("dividend-yield")?,
+    })
+}
+
+// American price next to the European one; the early exercise premium compares the two trees
+// so that their discretisation errors cancel
+fn run_tree(inputs: &Inputs, tree: Tree) -> Result<Vec<Output>, String> {
+    let option = vanilla(inputs)?;
+    let dividends = inputs.pairs("dividends")?;
+    let steps = inputs.count("steps")?;
+    let american = options::tree_price(&option, dividends, tree, steps, true)?;
+    let european = options::tree_price(&option, dividends, tree, steps, false)?;
+    let bsm_european = options::bsm_price(
+        option.kind,
+        option.spot - options::dividends_after(dividends, option.rate, 0.0),
+        option.strike,
+        option.time,
+        option.rate,
+        option.volatility,
+        option.dividend_yield,
+    );
+    Ok(vec![
+        Output::precise("american_price", "American Price", american, 4),
+        Output::precise("european_price", "European Price (tree)", european, 4),
+        Output::precise("bsm_price", "European Price (BSM)", bsm_european, 4),
+        Output::precise("early_exercise_premium", "Early Exercise Premium", american - european, 4),
+    ])
+}
+
+const VARIANCE_REDUCTIONS: &[&str] = &["none", "antithetic", "control-variate", "both"];
+
+// Inputs shared by the Monte Carlo pricers, placed after each pricer's own fields
+macro_rules! monte_carlo_fields {
+    ($($field:expr),* $(,)?) => {
+        &[
+            Field::choice("option-type", "option type", OPTION_KINDS),
+            Field::number("stock-price", "stock price").positive(),
+            Field::number("strike-price", "strike price").positive(),
+            Field::number("time-to-expiration", "time to expiration (years)").positive(),
+            Field::number("risk-free-rate", "risk-free rate"),
+            Field::number("volatility", "volatility").positive(),
+            Field::number("dividend-yield", "dividend yield"),
+            $($field,)*
+            Field::count("paths", "number of simulated paths"),
+            Field::count("steps", "number of monitoring dates"),
+            Field::count("seed", "random seed"),
+            Field::choice("variance-reduction", "variance reduction", VARIANCE_REDUCTIONS),
+        ]
+    };
+}
+
+pub const MONTE_CARLO_ASIAN: Calculator = Calculator {
+    id: "monte_carlo_asian",
+    function: None,
+    group: "option",
+    command: "asian",
+    title: "Asian Option - Monte Carlo",
+    fields: monte_carlo_fields![Field::choice("average", "average", &["arithmetic", "geometric"])],
+    checks: &[],
+    run: run_monte_carlo_asian,
+    report: None,
+};
+
+fn run_monte_carlo_asian(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let geometric = inputs.choice("average")? == "geometric";
+    run_monte_carlo(inputs, PathPayoff::Asian { geometric })
+}
+
+pub const MONTE_CARLO_LOOKBACK: Calculator = Calculator {
+    id: "monte_carlo_lookback",
+    function: None,
+    group: "option",
+    command: "lookback",
+    title: "Lookback Option - Monte Carlo",
+    fields: monte_carlo_fields![Field::choice("strike-type", "strike type", &["fixed", "floating"])],
+    checks: &[],
+    run: run_monte_carlo_lookback,
+    report: None,
+};
+
+fn run_monte_carlo_lookback(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let floating = inputs.choice("strike-type")? == "floating";
+    run_monte_carlo(inputs, PathPayoff::Lookback { floating })
+}
+
+pub const MONTE_CARLO_BARRIER: Calculator = Calculator {
+    id: "monte_carlo_barrier",
+    function: None,
+    group: "option",
+    command: "barrier",
+    title: "Barrier Option - Monte Carlo",
+    fields: monte_carlo_fields![
+        Field::choice("barrier-type", "barrier type", &["up-and-out", "up-and-in", "down-and-out", "down-and-in"]),
+        Field::number("barrier", "barrier level").positive(),
+    ],
+    checks: &[],
+    run: run_monte_carlo_barrier,
+    report: None,
+};
+
+fn run_monte_carlo_barrier(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let barrier_type = inputs.choice("barrier-type")?;
+    run_monte_carlo(
+        inputs,
+        PathPayoff::Barrier {
+            up: barrier_type.starts_with("up"),
+            knock_in: barrier_type.ends_with("in"),
+            level: inputs.num("barrier")?,
+        },
+    )
+}
+
+fn run_monte_carlo(inputs: &Inputs, payoff: PathPayoff) -> Result<Vec<Output>, String> {
+    let variance_reduction = inputs.choice("variance-reduction")?;
+    let simulation = Simulation {
+        paths: inputs.count("paths")?,
+        steps: inputs.count("steps")?,
+        seed: inputs.count("seed")? as u64,
+        antithetic: matches!(variance_reduction, "antithetic" | "both"),
+        control_variate: matches!(variance_reduction, "control-variate" | "both"),
+    };
+    let result = options::monte_carlo(&vanilla(inputs)?, payoff, &simulation)?;
+    Ok(vec![
+        Output::precise("price", "Price", result.price, 4),
+        Output::precise("standard_error", "Standard Error", result.standard_error, 6),
+        Output::precise("vanilla_price", "European Price (simulated)", result.vanilla_price, 4),
+        Output::precise("bsm_price", "European Price (BSM)", result.bsm_price, 4),
+    ])
+}
+
+pub const PUT_CALL_PARITY: Calculator = Calculator {
+    id: "put_call_parity",
+    function: None,
+    group: "option",
+    command: "parity",
+    title: "Put-Call Parity Check",
+    fields: &[
+        Field::number("call-price", "market call price").positive(),
+        Field::number("put-price", "market put price").positive(),
+        Field::number("stock-price", "stock price").positive(),
+        Field::number("strike-price", "strike price").positive(),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free rate"),
+        Field::number("dividend-yield", "dividend yield"),
+    ],
+    checks: &[],
+    run: run_put_call_parity,
+    report: Some(parity_report),
+};
+
+fn parity_inputs(inputs: &Inputs) -> Result<(f64, f64, f64, f64, f64), String> {
+    Ok((
+        inputs.num("stock-price")?,
+        inputs.num("strike-price")?,
+        inputs.num("time-to-expiration")?,
+        inputs.num("risk-free-rate")?,
+        inputs.num("dividend-yield")?,
+    ))
+}
+
+fn run_put_call_parity(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    parity_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+fn parity_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let (s, k, t, r, q) = parity_inputs(inputs)?;
+    let parity = options::put_call_parity(inputs.num("call-price")?, inputs.num("put-price")?, s, k, t, r, q);
+    let arbitrage_profit = if parity.violation.abs() < options::PARITY_TOLERANCE { 0.0 } else { parity.violation.abs() };
+    let mut outputs = vec![
+        Output::precise("violation", "Parity Violation (C - P - (Se^-qT - Ke^-rT))", parity.violation, 4),
+        Output::precise("arbitrage_profit", "Arbitrage Profit per Option", arbitrage_profit, 4),
+    ];
+    if let Some(rate) = parity.implied_rate {
+        outputs.push(Output::precise("implied_rate", "Implied Risk-Free Rate", rate, 6));
+        outputs.push(Output::precise("rate_difference", "Implied minus Input Rate", rate - r, 6));
+    }
+    if let Some(yield_) = parity.implied_dividend_yield {
+        outputs.push(Output::precise("implied_dividend_yield", "Implied Dividend Yield", yield_, 6));
+        outputs.push(Output::precise("dividend_difference", "Implied minus Input Dividend Yield", yield_ - q, 6));
+    }
+    Ok(Evaluation::new(outputs, options::parity_trade(parity.violation, s, k, t, r, q)))
+}
+
+pub const VOL_SURFACE: Calculator = Calculator {
+    id: "vol_surface",
+    function: None,
+    group: "option",
+    command: "vol-surface",
+    title: "Volatility Surface from Option Chain",
+    fields: &[
+        Field::text("chain-file", "option chain CSV file (strike, expiry, bid, ask, type)", surface::validate_chain_file),
+        Field::number("stock-price", "stock price").positive(),
+        Field::number("risk-free-rate", "risk-free rate"),
+        Field::number("dividend-yield", "dividend yield"),
+        Field::text("valuation-date", "valuation date for dated expiries (YYYY-MM-DD or 'today')", surface::validate_valuation_date),
+        Field::number("strike-price", "strike to interpolate the volatility at").positive(),
+        Field::number("time-to-expiration", "time to expiration to interpolate at (years)").positive(),
+    ],
+    checks: &[],
+    run: run_vol_surface,
+    report: Some(vol_surface_report),
+};
+
+fn build_surface(inputs: &Inputs) -> Result<Surface, String> {
+    let market = surface::Market {
+        spot: inputs.num("stock-price")?,
+        rate: inputs.num("risk-free-rate")?,
+        dividend_yield: inputs.num("dividend-yield")?,
+        valuation_date: surface::parse_valuation_date(inputs.text("valuation-date")?)?,
+    };
+    Surface::build(Path::new(inputs.text("chain-file")?), &market)
+}
+
+fn run_vol_surface(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    vol_surface_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+// The interpolated vol comes first so `ans` can be used as the Black-Scholes-Merton volatility
+fn vol_surface_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let surface = build_surface(inputs)?;
+    let outputs = vec![
+        Output::precise(
+            "interpolated_vol",
+            "Interpolated Volatility",
+            surface.vol(inputs.num("strike-price")?, inputs.num("time-to-expiration")?)?,
+            6,
+        ),
+        Output::precise("quotes_used", "Quotes Used", surface.quotes_used as f64, 0),
+        Output::precise("quotes_rejected", "Quotes Rejected", surface.rejected.len() as f64, 0),
+        Output::precise("expiries", "Expiries", surface.expiries() as f64, 0),
+    ];
+    let mut text = surface.grid();
+    for reason in surface.rejected.iter().take(10) {
+        text.push_str(&format!("Rejected {}\n", reason));
+    }
+    if surface.rejected.len() > 10 {
+        text.push_str(&format!("... and {} more rejected quotes\n", surface.rejected.len() - 10));
+    }
+    text.push_str("Enter 'ans' or a saved variable at the Black-Scholes-Merton volatility prompt to use this vol.\n");
+    Ok(Evaluation::new(outputs, text))
+}
+
+pub const BLACK76: Calculator = Calculator {
+    id: "black76",
+    function: None,
+    group: "option",
+    command: "black76",
+    title: "Black-76 (Futures and Forwards)",
+    fields: &[
+        Field::number("forward-price", "forward or futures price").positive(),
+        Field::number("strike-price", "strike price").positive(),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free (discount) rate"),
+        Field::number("volatility", "volatility").positive(),
+    ],
+    checks: &[],
+    run: run_black76,
+    report: None,
+};
+
+fn run_black76(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let (f, k, t, r, sigma) = forward_inputs(inputs)?;
+    let (call, put) = options::black76_greeks(f, k, t, r, sigma);
+    let mut outputs = vec![
+        Output::new("call_price", "Call Price", options::black76_price(OptionKind::Call, f, k, t, r, sigma)),
+        Output::new("put_price", "Put Price", options::black76_price(OptionKind::Put, f, k, t, r, sigma)),
+    ];
+    outputs.extend(greek_outputs(&call, &put, 4));
+    Ok(outputs)
+}
+
+pub const BLACK76_IMPLIED_VOL: Calculator = Calculator {
+    id: "black76_implied_vol",
+    function: None,
+    group: "option",
+    command: "black76-implied-vol",
+    title: "Black-76 Implied Volatility",
+    fields: &[
+        Field::choice("option-type", "option type", OPTION_KINDS),
+        Field::number("market-price", "market option price").positive(),
+        Field::number("forward-price", "forward or futures price").positive(),
+        Field::number("strike-price", "strike price").positive(),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free (discount) rate"),
+    ],
+    checks: &[],
+    run: run_black76_implied_vol,
+    report: None,
+};
+
+fn run_black76_implied_vol(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let solution = options::black76_implied_vol(
+        OptionKind::from_choice(inputs.choice("option-type")?),
+        inputs.num("market-price")?,
+        inputs.num("forward-price")?,
+        inputs.num("strike-price")?,
+        inputs.num("time-to-expiration")?,
+        inputs.num("risk-free-rate")?,
+    )?;
+    Ok(implied_vol_outputs(&solution))
+}
+
+pub const BACHELIER: Calculator = Calculator {
+    id: "bachelier",
+    function: None,
+    group: "option",
+    command: "bachelier",
+    title: "Bachelier (Normal Model)",
+    fields: &[
+        Field::number("forward-price", "forward price or rate (may be negative)"),
+        Field::number("strike-price", "strike (may be negative)"),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free (discount) rate"),
+        Field::number("volatility", "normal volatility (absolute, e.g. 0.01 for 100bp)").positive(),
+    ],
+    checks: &[],
+    run: run_bachelier,
+    report: None,
+};
+
+fn run_bachelier(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let (f, k, t, r, sigma) = forward_inputs(inputs)?;
+    let (call, put) = options::bachelier_greeks(f, k, t, r, sigma);
+    let mut outputs = vec![
+        Output::precise("call_price", "Call Price", options::bachelier_price(OptionKind::Call, f, k, t, r, sigma), 6),
+        Output::precise("put_price", "Put Price", options::bachelier_price(OptionKind::Put, f, k, t, r, sigma), 6),
+    ];
+    outputs.extend(greek_outputs(&call, &put, 6));
+    Ok(outputs)
+}
+
+pub const BACHELIER_IMPLIED_VOL: Calculator = Calculator {
+    id: "bachelier_implied_vol",
+    function: None,
+    group: "option",
+    command: "bachelier-implied-vol",
+    title: "Bachelier Implied Normal Volatility",
+    fields: &[
+        Field::choice("option-type", "option type", OPTION_KINDS),
+        Field::number("market-price", "market option price").positive(),
+        Field::number("forward-price", "forward price or rate (may be negative)"),
+        Field::number("strike-price", "strike (may be negative)"),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free (discount) rate"),
+    ],
+    checks: &[],
+    run: run_bachelier_implied_vol,
+    report: None,
+};
+
+fn run_bachelier_implied_vol(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let solution = options::bachelier_implied_vol(
+        OptionKind::from_choice(inputs.choice("option-type")?),
+        inputs.num("market-price")?,
+        inputs.num("forward-price")?,
+        inputs.num("strike-price")?,
+        inputs.num("time-to-expiration")?,
+        inputs.num("risk-free-rate")?,
+    )?;
+    Ok(implied_vol_outputs(&solution))
+}
+
+pub const STRATEGY: Calculator = Calculator {
+    id: "strategy",
+    function: None,
+    group: "option",
+    command: "strategy",
+    title: "Option Strategy Builder",
+    fields: &[
+        Field::text("legs", strategy::LEGS_HELP, strategy::validate_legs),
+        Field::number("stock-price", "stock price").positive(),
+        Field::number("time-to-expiration", "time to expiration (years)").positive(),
+        Field::number("risk-free-rate", "risk-free rate"),
+        Field::number("volatility", "volatility").positive(),
+        Field::number("dividend-yield", "dividend yield"),
+    ],
+    checks: &[],
+    run: run_strategy,
+    report: Some(strategy_chart),
+};
+
+// The first breakevens are also outputs, for `ans` and saved variables; the report lists them all
+const BREAKEVENS: [(&str, &str); 4] = [
+    ("breakeven_1", "Breakeven 1"),
+    ("breakeven_2", "Breakeven 2"),
+    ("breakeven_3", "Breakeven 3"),
+    ("breakeven_4", "Breakeven 4"),
+];
+
+fn strategy_market(inputs: &Inputs) -> Result<Market, String> {
+    Ok(Market {
+        spot: inputs.num("stock-price")?,
+        time: inputs.num("time-to-expiration")?,
+        rate: inputs.num("risk-free-rate")?,
+        volatility: inputs.num("volatility")?,
+        dividend_yield: inputs.num("dividend-yield")?,
+    })
+}
+
+fn run_strategy(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    strategy_chart(inputs).map(|evaluation| evaluation.outputs)
+}
+
+fn strategy_chart(inputs: &Inputs) -> Result<Evaluation, String> {
+    let legs = strategy::parse_legs(inputs.text("legs")?)?;
+    let market = strategy_market(inputs)?;
+    let analysis = strategy::analyse(&legs, &market);
+    let mut outputs = vec![
+        Output::precise("net_premium", "Net Premium (paid if positive)", analysis.net_premium, 4),
+        Output::precise("delta", "Delta", analysis.delta, 4),
+        Output::precise("gamma", "Gamma", analysis.gamma, 4),
+        Output::precise("vega", "Vega", analysis.vega, 4),
+        Output::precise("theta", "Theta (per year)", analysis.theta, 4),
+        Output::precise("theta_day", "Theta (per day)", analysis.theta / 365.0, 4),
+        Output::precise("rho", "Rho", analysis.rho, 4),
+        Output::precise("max_profit", "Max Profit", analysis.max_profit, 4),
+        Output::precise("max_loss", "Max Loss", analysis.max_loss, 4),
+    ];
+    for ((name, label), breakeven) in BREAKEVENS.iter().zip(&analysis.breakevens) {
+        outputs.push(Output::precise(name, label, *breakeven, 4));
+    }
+    let mut report = match analysis.breakevens.len() {
+        0 => String::from("\nNo breakeven: the position never ends flat at expiry.\n"),
+        _ => {
+            let breakevens: Vec<String> = analysis.breakevens.iter().map(|breakeven| format!("{:.4}", breakeven)).collect();
+            format!("\nBreakevens at expiry: {}\n", breakevens.join(", "))
+        }
+    };
+    report.push_str(&strategy::payoff_chart(&legs, &market));
+    Ok(Evaluation::new(outputs, report))
+}
+
+fn forward_inputs(inputs: &Inputs) -> Result<(f64, f64, f64, f64, f64), String> {
+    Ok((
+        inputs.num("forward-price")?,
+        inputs.num("strike-price")?,
+        inputs.num("time-to-expiration")?,
+        inputs.num("risk-free-rate")?,
+        inputs.num("volatility")?,
+    ))
+}
+
+// Call/put pairs of Greeks, shown as a table by `format_outputs`
+fn greek_outputs(call: &Greeks, put: &Greeks, decimals: usize) -> Vec<Output> {
+    vec![
+        Output::precise("call_delta", "Call Delta", call.delta, decimals),
+        Output::precise("put_delta", "Put Delta", put.delta, decimals),
+        Output::precise("call_gamma", "Call Gamma", call.gamma, decimals),
+        Output::precise("put_gamma", "Put Gamma", put.gamma, decimals),
+        Output::precise("call_vega", "Call Vega", call.vega, decimals),
+        Output::precise("put_vega", "Put Vega", put.vega, decimals),
+        Output::precise("call_theta", "Call Theta (per year)", call.theta, decimals),
+        Output::precise("put_theta", "Put Theta (per year)", put.theta, decimals),
+        Output::precise("call_theta_day", "Call Theta (per day)", call.theta / 365.0, decimals),
+        Output::precise("put_theta_day", "Put Theta (per day)", put.theta / 365.0, decimals),
+        Output::precise("call_rho", "Call Rho", call.rho, decimals),
+        Output::precise("put_rho", "Put Rho", put.rho, decimals),
+    ]
+}
+
+// Bonds
+
+// Bond inputs shared by the price and yield calculators; prices are per 100 of face value
+const SETTLEMENT_DATE: Field = Field::text("settlement-date", "settlement date (YYYY-MM-DD)", bond::validate_date);
+const MATURITY_DATE: Field = Field::text("maturity-date", "maturity date (YYYY-MM-DD)", bond::validate_date);
+const COUPON_RATE: Field = Field::number("coupon-rate", "annual coupon rate (as a decimal)");
+const COUPON_FREQUENCY: Field = Field::choice("frequency", "coupon frequency", bond::FREQUENCIES).with_default("semiannual");
+const BOND_DAY_COUNT: Field =
+    Field::choice("day-count", "day count convention (act/act is ACT/ACT ICMA for bonds)", daycount::DAY_COUNTS).with_default("30/360");
+
+fn bond(inputs: &Inputs) -> Result<Bond, String> {
+    Bond::new(
+        bond::parse_date(inputs.text("settlement-date")?)?,
+        bond::parse_date(inputs.text("maturity-date")?)?,
+        inputs.num("coupon-rate")?,
+        bond::frequency_from_choice(inputs.choice("frequency")?),
+        DayCount::from_choice(inputs.choice("day-count")?),
+    )
+}
+
+pub const BOND_PRICE: Calculator = Calculator {
+    id: "bond_price",
+    function: None,
+    group: "bond",
+    command: "price",
+    title: "Bond Price from Yield",
+    fields: &[
+        SETTLEMENT_DATE,
+        MATURITY_DATE,
+        COUPON_RATE,
+        Field::number("yield", "yield to maturity (annual, as a decimal)"),
+        COUPON_FREQUENCY,
+        BOND_DAY_COUNT,
+    ],
+    checks: &[],
+    run: run_bond_price,
+    report: None,
+};
+
+fn run_bond_price(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let bond = bond(inputs)?;
+    let quote = bond.price(inputs.num("yield")?);
+    Ok(vec![
+        Output::precise("clean_price", "Clean Price", quote.clean, 4),
+        Output::precise("dirty_price", "Dirty Price", quote.dirty, 4),
+        Output::precise("accrued_interest", "Accrued Interest", quote.accrued, 4),
+        Output::precise("current_yield", "Current Yield", 100.0 * bond.coupon_rate / quote.clean, 6),
+    ])
+}
+
+pub const BOND_YIELD: Calculator = Calculator {
+    id: "bond_yield",
+    function: None,
+    group: "bond",
+    command: "yield",
+    title: "Bond Yield to Maturity and Call",
+    fields: &[
+        SETTLEMENT_DATE,
+        MATURITY_DATE,
+        COUPON_RATE,
+        Field::number("clean-price", "clean price (per 100 of face value)").positive(),
+        COUPON_FREQUENCY,
+        BOND_DAY_COUNT,
+        Field::text("call-date", "call date (YYYY-MM-DD, or 'none')", bond::validate_optional_date).with_default("none"),
+        Field::number("call-price", "call price (per 100 of face value)").positive().with_default("100"),
+    ],
+    checks: &[],
+    run: run_bond_yield,
+    report: None,
+};
+
+fn run_bond_yield(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    let bond = bond(inputs)?;
+    let clean = inputs.num("clean-price")?;
+    let no_yield = |to: &str| format!("no yield to {} between -99% and 1000% a period matches a clean price of {}", to, clean);
+    let ytm = bond.yield_to(clean, bond.maturity, 100.0).ok_or_else(|| no_yield("maturity"))?;
+    let accrued = bond.accrued_interest();
+    let mut outputs = vec![Output::precise("ytm", "Yield to Maturity", ytm, 6)];
+
+    let call_date = inputs.text("call-date")?;
+    if !call_date.eq_ignore_ascii_case("none") {
+        let call_date = bond::parse_date(call_date)?;
+        if call_date <= bond.settlement || call_date > bond.maturity {
+            return Err(format!("call date {} must be after settlement and no later than maturity", call_date));
+        }
+        let ytc = bond.yield_to(clean, call_date, inputs.num("call-price")?).ok_or_else(|| no_yield("call"))?;
+        outputs.push(Output::precise("ytc", "Yield to Call", ytc, 6));
+        outputs.push(Output::precise("yield_to_worst", "Yield to Worst", ytm.min(ytc), 6));
+    }
+    outputs.push(Output::precise("dirty_price", "Dirty Price", clean + accrued, 4));
+    outputs.push(Output::precise("accrued_interest", "Accrued Interest", accrued, 4));
+    outputs.push(Output::precise("current_yield", "Current Yield", 100.0 * bond.coupon_rate / clean, 6));
+    Ok(outputs)
+}
+
+pub const BOND_DURATION: Calculator = Calculator {
+    id: "bond_duration",
+    function: None,
+    group: "bond",
+    command: "duration",
+    title: "Bond Duration, Convexity and DV01",
+    fields: &[
+        SETTLEMENT_DATE,
+        MATURITY_DATE,
+        COUPON_RATE,
+        Field::number("yield", "yield to maturity (annual, as a decimal)"),
+        COUPON_FREQUENCY,
+        BOND_DAY_COUNT,
+        ZERO_CURVE,
+        RATE_SHOCK,
+    ],
+    checks: &[],
+    run: run_bond_duration,
+    report: Some(bond_duration_report),
+};
+
+// The curve's zero rates are compounded at the coupon frequency, like the yield
+fn bond_analytics(inputs: &Inputs) -> Result<duration::Analytics, String> {
+    let bond = bond(inputs)?;
+    let frequency = bond.frequency as f64;
+    let flows: Vec<(f64, f64)> = bond.flows(bond.maturity, 100.0).iter().map(|(periods, amount)| (periods / frequency, *amount)).collect();
+    let curve = zero_curve(inputs, inputs.num("yield")?)?;
+    duration::analyse(&flows, &curve, duration::Compounding::Periodic(frequency), inputs.num("shock")?)
+}
+
+fn run_bond_duration(inputs: &Inputs) -> Result<Vec<Output>, String> {
+    bond_duration_report(inputs).map(|evaluation| evaluation.outputs)
+}
+
+fn bond_duration_report(inputs: &Inputs) -> Result<Evaluation, String> {
+    let analytics = bond_analytics(inputs)?;
+    let discounting = if inputs.pairs("curve")?.is_empty() { "the yield" } else { "the zero curve" };
+    Ok(Evaluation::new(
+        duration_outputs(&analytics, Output::precise("dirty_price", "Dirty Price", analytics.present_value, 4)),
+        duration_report(&analytics, discounting),
+    ))
+}
@@ -0,0 +1,262 @@
+use std::path::Path;
+
+use chrono::{Datelike, Months, NaiveDate};
+
+// Dated cash flow lists: flexible date and amount parsing, repetition and CSV import from
+// hand-made files or bank/brokerage exports
+
+pub type CashFlow = (f64, String); // amount and YYYY-MM-DD date
+
+// Tried in order; month-first wins over day-first for slashed dates
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%m/%d/%y", "%d.%m.%Y", "%d-%b-%Y", "%d %b %Y", "%b %d, %Y", "%d %B %Y", "%B %d, %Y", "%Y%m%d",
+];
+
+// Four-digit years only, so coupon schedules, business-day rolls and ACT/ACT year fractions
+// stay well inside chrono's date range
+fn in_range(date: &NaiveDate) -> bool {
+    (1000..=9999).contains(&date.year())
+}
+
+// A date in any of DATE_FORMATS, ignoring a trailing time as in "2024-01-31 16:00:00"
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim().trim_matches('"');
+    let candidates = [text, text.split_whitespace().next().unwrap_or(""), text.split('T').next().unwrap_or("")];
+    candidates
+        .iter()
+        .find_map(|candidate| DATE_FORMATS.iter().find_map(|format| NaiveDate::parse_from_str(candidate, format).ok().filter(in_range)))
+}
+
+// A YYYY-MM-DD date, as cash flow lists store them
+pub fn parse_iso_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok().filter(in_range)
+}
+
+// An amount as exports write it: currency symbols, thousands separators, "(1,234.56)" or a
+// trailing minus for negatives and a CR/DR suffix
+pub fn parse_amount(text: &str) -> Option<f64> {
+    let mut text = text.trim().to_uppercase();
+    let mut sign = 1.0;
+    if let Some(stripped) = text.strip_suffix("DR") {
+        sign = -1.0;
+        text = stripped.to_string();
+    } else if let Some(stripped) = text.strip_suffix("CR") {
+        text = stripped.to_string();
+    }
+    let mut text: String = text.chars().filter(|c| !matches!(c, '$' | '€' | '£' | '¥' | ',' | ' ' | '"')).collect();
+    if text.starts_with('(') && text.ends_with(')') {
+        sign = -sign;
+        text = text[1..text.len() - 1].to_string();
+    }
+    if let Some(stripped) = text.strip_suffix('-') {
+        sign = -sign;
+        text = stripped.to_string();
+    }
+    text.parse::<f64>().ok().filter(|amount| amount.is_finite()).map(|amount| sign * amount)
+}
+
+#[derive(Clone, Copy)]
+pub enum Frequency {
+    Monthly,
+    Quarterly,
+    Annually,
+}
+
+impl Frequency {
+    pub fn parse(text: &str) -> Option<Frequency> {
+        match text.to_lowercase().as_str() {
+            "monthly" | "month" | "m" => Some(Frequency::Monthly),
+            "quarterly" | "quarter" | "q" => Some(Frequency::Quarterly),
+            "annually" | "annual" | "yearly" | "year" | "a" | "y" => Some(Frequency::Annually),
+            _ => None,
+        }
+    }
+
+    fn months(self) -> u32 {
+        match self {
+            Frequency::Monthly => 1,
+            Frequency::Quarterly => 3,
+            Frequency::Annually => 12,
+        }
+    }
+}
+
+// `count` copies of a flow following it at the given frequency; month ends stay month ends
+pub fn repeat(flow: &CashFlow, frequency: Frequency, count: u32) -> Result<Vec<CashFlow>, String> {
+    let start = parse_iso_date(&flow.1).ok_or_else(|| format!("'{}' is not a valid YYYY-MM-DD date", flow.1))?;
+    let month_end = start.succ_opt().is_some_and(|next| next.month0() != start.month0());
+    (1..=count)
+        .map(|n| {
+            let date = start
+                .checked_add_months(Months::new(frequency.months() * n))
+                .ok_or_else(|| format!("repeating from {} runs past the last supported date", start))?;
+            let date = if month_end { last_day_of_month(date) } else { date };
+            Ok((flow.0, date.format("%Y-%m-%d").to_string()))
+        })
+        .collect()
+}
+
+fn last_day_of_month(date: NaiveDate) -> NaiveDate {
+    let mut last = date;
+    while let Some(next) = last.succ_opt().filter(|next| next.month0() == date.month0()) {
+        last = next;
+    }
+    last
+}
+
+// Stable, so flows on the same date keep their order
+pub fn sort_by_date(flows: &mut [CashFlow]) {
+    flows.sort_by_key(|(_, date)| parse_iso_date(date));
+}
+
+// Where the amount of a row comes from
+enum AmountColumns {
+    Amount(usize),
+    InOut(usize, usize), // money in and money out, e.g. credit/debit or distribution/contribution
+}
+
+const IN_HEADERS: &[&str] = &["credit", "deposit", "paid in", "inflow", "distribution", "receipt"];
+const OUT_HEADERS: &[&str] = &["debit", "withdrawal", "paid out", "outflow", "contribution", "payment", "capital call"];
+
+fn find_header(cells: &[String], names: &[&str]) -> Option<usize> {
+    cells.iter().position(|cell| names.iter().any(|name| cell.contains(name)))
+}
+
+// Recognises a header row by its date column and its amount or in/out columns
+fn header_columns(record: &csv::StringRecord) -> Option<(usize, AmountColumns)> {
+    let cells: Vec<String> = record.iter().map(|cell| cell.trim().to_lowercase()).collect();
+    let date = find_header(&cells, &["date"])?;
+    if let Some(amount) = cells.iter().position(|cell| cell.contains("amount") || ["cash flow", "cashflow", "value", "net"].contains(&cell.as_str())) {
+        return Some((date, AmountColumns::Amount(amount)));
+    }
+    Some((date, AmountColumns::InOut(find_header(&cells, IN_HEADERS)?, find_header(&cells, OUT_HEADERS)?)))
+}
+
+// A header-less row: the first date cell and the first other cell holding an amount
+fn data_columns(record: &csv::StringRecord) -> Option<(usize, AmountColumns)> {
+    let date = record.iter().position(|cell| parse_date(cell).is_some())?;
+    let amount = record.iter().enumerate().position(|(index, cell)| index != date && parse_amount(cell).is_some())?;
+    Some((date, AmountColumns::Amount(amount)))
+}
+
+fn row_flow(record: &csv::StringRecord, date: usize, amount: &AmountColumns) -> Result<CashFlow, String> {
+    let cell = |index: usize| record.get(index).unwrap_or("").trim();
+    let parsed = parse_date(cell(date)).ok_or_else(|| format!("invalid date '{}'", cell(date)))?;
+    let amount = match *amount {
+        AmountColumns::Amount(index) => parse_amount(cell(index)).ok_or_else(|| format!("invalid amount '{}'", cell(index)))?,
+        AmountColumns::InOut(money_in, money_out) => match (parse_amount(cell(money_in)), parse_amount(cell(money_out))) {
+            (None, None) => return Err("no amount".to_string()),
+            (money_in, money_out) => money_in.unwrap_or(0.0).abs() - money_out.unwrap_or(0.0).abs(),
+        },
+    };
+    Ok((amount, parsed.format("%Y-%m-%d").to_string()))
+}
+
+pub struct Import {
+    pub flows: Vec<CashFlow>, // sorted by date
+    pub skipped: Vec<String>, // "line N: reason" for rows after the header that were not flows
+}
+
+// Reads flows from a CSV file. Lines before the header row (account details in many bank
+// exports) are ignored; without a header, rows with a date and an amount are taken as flows.
+pub fn import(path: &Path) -> Result<Import, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut columns: Option<(usize, AmountColumns)> = None;
+    let mut flows = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        // Blank lines are not records, so line numbers come from the reader
+        let line = |position: Option<&csv::Position>| position.map_or(index as u64 + 1, |position| position.line());
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                skipped.push(format!("line {}: {}", line(e.position()), e));
+                continue;
+            }
+        };
+        let line = line(record.position());
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let Some((date, amount)) = &columns else {
+            if let Some(found) = header_columns(&record) {
+                columns = Some(found);
+            } else if let Some(found) = data_columns(&record) {
+                flows.push(row_flow(&record, found.0, &found.1)?);
+                columns = Some(found);
+            }
+            continue;
+        };
+        match row_flow(&record, *date, amount) {
+            Ok(flow) => flows.push(flow),
+            Err(message) => skipped.push(format!("line {}: {}", line, message)),
+        }
+    }
+    if flows.is_empty() {
+        return Err(format!(
+            "no cash flows found in {} (expected a date column and an amount, credit/debit or distribution/contribution columns)",
+            path.display()
+        ));
+    }
+    sort_by_date(&mut flows);
+    Ok(Import { flows, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    #[test]
+    fn amounts_as_exports_write_them() {
+        assert_eq!(parse_amount("1,234.56"), Some(1234.56));
+        assert_eq!(parse_amount("$1,234,567"), Some(1234567.0));
+        assert_eq!(parse_amount("(1,234.56)"), Some(-1234.56));
+        assert_eq!(parse_amount("€ 99.50-"), Some(-99.5));
+        assert_eq!(parse_amount("250.00 CR"), Some(250.0));
+        assert_eq!(parse_amount("250.00 dr"), Some(-250.0));
+        assert_eq!(parse_amount("(250.00) DR"), Some(250.0));
+        assert_eq!(parse_amount("\"-42\""), Some(-42.0));
+        assert_eq!(parse_amount("n/a"), None);
+        assert_eq!(parse_amount(""), None);
+    }
+
+    #[test]
+    fn dates_in_every_supported_format() {
+        let expected = NaiveDate::from_ymd_opt(2024, 3, 5);
+        for text in ["2024-03-05", "2024/03/05", "03/05/2024", "03/05/24", "05.03.2024", "05-Mar-2024", "5 Mar 2024", "Mar 5, 2024", "5 March 2024", "March 5, 2024", "20240305", "2024-03-05 16:00:00", "2024-03-05T16:00:00", " \"2024-03-05\" "] {
+            assert_eq!(parse_date(text), expected, "{}", text);
+        }
+        assert_eq!(parse_date("13/05/2024"), None);
+        assert_eq!(parse_date("0099-01-01"), None);
+        assert_eq!(parse_iso_date("03/05/2024"), None);
+        assert_eq!(parse_iso_date(" 2024-03-05 "), expected);
+    }
+
+    #[test]
+    fn repeats_keep_month_ends() {
+        let dates = |flow: (f64, &str), frequency, count| -> Vec<String> {
+            repeat(&(flow.0, flow.1.to_string()), frequency, count).unwrap().into_iter().map(|(_, date)| date).collect()
+        };
+        assert_eq!(dates((100.0, "2024-01-31"), Frequency::Monthly, 3), ["2024-02-29", "2024-03-31", "2024-04-30"]);
+        assert_eq!(dates((100.0, "2023-02-28"), Frequency::Annually, 1), ["2024-02-29"]);
+        assert_eq!(dates((100.0, "2024-01-30"), Frequency::Monthly, 2), ["2024-02-29", "2024-03-30"]);
+        assert_eq!(dates((100.0, "2024-01-15"), Frequency::Quarterly, 2), ["2024-04-15", "2024-07-15"]);
+        assert!(repeat(&(100.0, "2024-1-15x".to_string()), Frequency::Monthly, 1).is_err());
+    }
+
+    #[test]
+    fn import_finds_the_header_below_account_details() {
+        let import = import(&fixture("bank_export.csv")).unwrap();
+        let flows: Vec<(f64, &str)> = import.flows.iter().map(|(amount, date)| (*amount, date.as_str())).collect();
+        assert_eq!(flows, [(-500.0, "2024-01-01"), (-10000.0, "2024-03-15"), (1250.0, "2024-06-30"), (9500.0, "2024-12-31")]);
+        assert_eq!(import.skipped, ["line 7: invalid date 'not a date'"]);
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::{Path, PathBuf};
+
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+
+use crate::batch;
+use crate::cashflows::{self, CashFlow};
+use crate::export::export;
+use crate::expr;
+use crate::history::Record;
+
+use crate::calculators::{self, CALCULATORS, Calculator, Evaluation, Field, FieldKind, GROUPS, Inputs, Value, format_result, parse_cashflow, parse_choice, parse_pair, result_json};
+
+// Command line interface - every calculator is reachable as `finance_cli <group> <command> --flags`
+
+pub fn build_cli() -> Command {
+    let mut cli = Command::new("finance_cli")
+        .about("IFCH - Iain's Financial Calculation Helper")
+        .long_about("Run without a subcommand to start the interactive menu.")
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .global(true)
+                .value_name("FORMAT")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help("Result format for subcommands"),
+        )
+        .arg(
+            Arg::new("export")
+                .long("export")
+                .global(true)
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write the session's results with their inputs to a .csv, .json or .md file"),
+        );
+
+    for (group, about) in GROUPS {
+        let mut group_cmd = Command::new(*group)
+            .about(*about)
+            .subcommand_required(true)
+            .arg_required_else_help(true);
+        for calc in CALCULATORS.iter().filter(|calc| calc.group == *group) {
+            group_cmd = group_cmd.subcommand(calculator_command(calc));
+        }
+        cli = cli.subcommand(group_cmd);
+    }
+
+    cli.subcommand(batch_command())
+}
+
+fn batch_command() -> Command {
+    Command::new("batch")
+        .about("Evaluate every row of a CSV or JSON file")
+        .long_about(
+            "Evaluate every row of a CSV or JSON file. Columns are named after the calculator's \
+             flags (e.g. current_assets), and a 'calculator' column may pick the calculator per row \
+             by id or ifch function name (e.g. current_r). Numbers may be written as at the prompts \
+             (5%, 1.2m, $1,234.56, 100*1.05^2), but 'ans' and saved variables are not available. \
+             Rows that fail get an 'error' column.",
+        )
+        .arg(
+            Arg::new("input")
+                .required(true)
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Input .csv or .json file"),
+        )
+        .arg(
+            Arg::new("calculator")
+                .long("calculator")
+                .short('c')
+                .value_name("ID")
+                .help("Calculator for rows without a 'calculator' column, e.g. current_ratio or current_r"),
+        )
+        .arg(
+            Arg::new("out")
+                .long("out")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Output .csv or .json file (defaults to stdout in the input format)"),
+        )
+}
+
+fn run_batch(matches: &ArgMatches) -> i32 {
+    let calculator = match matches.get_one::<String>("calculator") {
+        Some(name) => match calculators::find(name) {
+            Some(calc) => Some(calc),
+            None => {
+                eprintln!("Unknown calculator '{}'", name);
+                return 1;
+            }
+        },
+        None => None,
+    };
+    let input = matches.get_one::<PathBuf>("input").unwrap();
+    let output = matches.get_one::<PathBuf>("out");
+
+    match batch::run(input, output.map(PathBuf::as_path), calculator) {
+        Ok(summary) => {
+            eprintln!("Processed {} rows, {} failed", summary.rows, summary.failed);
+            if summary.failed == 0 { 0 } else { 1 }
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
+
+fn calculator_command(calc: &'static Calculator) -> Command {
+    let mut cmd = Command::new(calc.command).about(calc.title);
+    for field in calc.fields {
+        cmd = cmd.arg(field_arg(field));
+    }
+    cmd
+}
+
+fn field_arg(field: &'static Field) -> Arg {
+    let mut arg = Arg::new(field.name)
+        .long(field.name)
+        .help(field.label)
+        .required(field.default.is_none())
+        .allow_hyphen_values(true);
+    if let Some(default) = field.default {
+        arg = arg.default_value(default);
+    }
+    match field.kind {
+        FieldKind::Number => arg.value_name("NUMBER").value_parser(expr::evaluate_constant),
+        FieldKind::Count => arg.value_name("COUNT").value_parser(value_parser!(u32)),
+        FieldKind::CashFlows => arg
+            .value_name("AMOUNT,DATE|FILE")
+            .action(ArgAction::Append)
+            .value_parser(parse_cashflow_arg),
+        FieldKind::Choice(options) => arg
+            .value_name("CHOICE")
+            .ignore_case(true)
+            .value_parser(PossibleValuesParser::new(options.iter().copied())),
+        FieldKind::Numbers => arg
+            .value_name("NUMBER")
+            .action(ArgAction::Append)
+            .value_parser(expr::evaluate_constant),
+        FieldKind::Pairs(format) => arg
+            .value_name(format)
+            .required(false)
+            .action(ArgAction::Append)
+            .value_parser(parse_pair),
+        FieldKind::Text(validate) => arg
+            .value_name("TEXT")
+            .value_parser(move |s: &str| validate(s).map(|()| s.trim().to_string())),
+    }
+}
+
+// One AMOUNT,DATE flow, or every flow of a CSV file or bank/brokerage export
+fn parse_cashflow_arg(s: &str) -> Result<Vec<CashFlow>, String> {
+    if Path::new(s).is_file() {
+        return cashflows::import(Path::new(s)).map(|import| import.flows);
+    }
+    parse_cashflow(s).map(|flow| vec![flow])
+}
+
+fn find_calculator(group: &str, command: &str) -> &'static Calculator {
+    CALCULATORS
+        .iter()
+        .copied()
+        .find(|calc| calc.group == group && calc.command == command)
+        .expect("subcommand is registered")
+}
+
+fn collect_inputs(calc: &'static Calculator, matches: &ArgMatches) -> Inputs {
+    let mut inputs = Inputs::new();
+    for field in calc.fields {
+        let value = match field.kind {
+            FieldKind::Number => Value::Number(*matches.get_one::<f64>(field.name).unwrap()),
+            FieldKind::Count => Value::Count(*matches.get_one::<u32>(field.name).unwrap()),
+            FieldKind::CashFlows => Value::CashFlows(
+                matches
+                    .get_many::<Vec<CashFlow>>(field.name)
+                    .unwrap()
+                    .flatten()
+                    .cloned()
+                    .collect(),
+            ),
+            FieldKind::Choice(options) => {
+                let option = matches.get_one::<String>(field.name).unwrap();
+                Value::Choice(parse_choice(options, option).expect("clap checked the possible values"))
+            }
+            FieldKind::Text(_) => Value::Text(matches.get_one::<String>(field.name).unwrap().clone()),
+            FieldKind::Numbers => Value::Numbers(matches.get_many::<f64>(field.name).unwrap().copied().collect()),
+            FieldKind::Pairs(_) => Value::Pairs(
+                matches
+                    .get_many::<(f64, f64)>(field.name)
+                    .map(|pairs| pairs.copied().collect())
+                    .unwrap_or_default(),
+            ),
+        };
+        inputs.push(field, value);
+    }
+    inputs
+}
+
+// Runs the subcommand given on the command line and returns the process exit code
+pub fn run(matches: &ArgMatches) -> i32 {
+    let (group, group_matches) = matches.subcommand().expect("subcommand is present");
+    if group == "batch" {
+        return run_batch(group_matches);
+    }
+    let (command, calc_matches) = group_matches.subcommand().expect("subcommand is required");
+    let calc = find_calculator(group, command);
+
+    let inputs = collect_inputs(calc, calc_matches);
+    let json = matches.get_one::<String>("output").map(String::as_str) == Some("json");
+    // Reports are only worked out for text output
+    let result = if json {
+        calculators::evaluate(calc, &inputs).map(|outputs| Evaluation::new(outputs, String::new()))
+    } else {
+        calculators::evaluate_with_report(calc, &inputs)
+    };
+
+    if let (Some(path), Ok(evaluation)) = (matches.get_one::<PathBuf>("export"), &result)
+        && let Err(message) = export(&[Record::new(1, calc, &inputs, &evaluation.outputs)], path)
+    {
+        eprintln!("Export failed: {}", message);
+        return 1;
+    }
+
+    if json {
+        let result = result.map(|evaluation| evaluation.outputs);
+        println!("{}", result_json(calc, &inputs, &result));
+        return if result.is_ok() { 0 } else { 1 };
+    }
+
+    match result {
+        Ok(evaluation) => {
+            print!("{}", format_result(&evaluation));
+            if let Err(message) = calculators::write_files(&evaluation) {
+                eprintln!("Export failed: {}", message);
+                return 1;
+            }
+            0
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
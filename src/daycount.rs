@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use crate::cashflows;
+
+// Day-count conventions and business-day adjustment for dated cash flows
+
+pub const DAY_COUNTS: &[&str] = &["act/365f", "act/360", "30/360", "30e/360", "act/act"];
+pub const ADJUSTMENTS: &[&str] = &["none", "following", "modified-following"];
+
+#[derive(Clone, Copy)]
+pub enum DayCount {
+    Act365Fixed,
+    Act360,
+    Thirty360Us, // 30/360 US (bond basis) with the end-of-February rules
+    Thirty360European,
+    ActActIsda, // bonds accrue it per coupon period instead, as ACT/ACT ICMA
+}
+
+impl DayCount {
+    pub fn from_choice(choice: &str) -> DayCount {
+        match choice {
+            "act/360" => DayCount::Act360,
+            "30/360" => DayCount::Thirty360Us,
+            "30e/360" => DayCount::Thirty360European,
+            "act/act" => DayCount::ActActIsda,
+            _ => DayCount::Act365Fixed,
+        }
+    }
+
+    // Years from `start` to `end`, negative when `end` comes first
+    pub fn year_fraction(self, start: NaiveDate, end: NaiveDate) -> f64 {
+        if end < start {
+            return -self.year_fraction(end, start);
+        }
+        let days = (end - start).num_days() as f64;
+        match self {
+            DayCount::Act365Fixed => days / 365.0,
+            DayCount::Act360 => days / 360.0,
+            DayCount::Thirty360Us => {
+                let (mut d1, mut d2) = (start.day() as i32, end.day() as i32);
+                if is_last_of_february(start) {
+                    if is_last_of_february(end) {
+                        d2 = 30;
+                    }
+                    d1 = 30;
+                }
+                if d2 == 31 && d1 >= 30 {
+                    d2 = 30;
+                }
+                d1 = d1.min(30);
+                thirty_360(start, end, d1, d2)
+            }
+            DayCount::Thirty360European => thirty_360(start, end, (start.day() as i32).min(30), (end.day() as i32).min(30)),
+            DayCount::ActActIsda => {
+                // Days in each calendar year over that year's length
+                let mut fraction = 0.0;
+                let mut from = start;
+                while from < end {
+                    let next_year = NaiveDate::from_ymd_opt(from.year() + 1, 1, 1).expect("valid date");
+                    let to = next_year.min(end);
+                    let year_length = if from.leap_year() { 366.0 } else { 365.0 };
+                    fraction += (to - from).num_days() as f64 / year_length;
+                    from = to;
+                }
+                fraction
+            }
+        }
+    }
+}
+
+fn is_last_of_february(date: NaiveDate) -> bool {
+    date.month() == 2 && date.succ_opt().is_some_and(|next| next.month() == 3)
+}
+
+fn thirty_360(start: NaiveDate, end: NaiveDate, d1: i32, d2: i32) -> f64 {
+    let years = (end.year() - start.year()) as f64;
+    let months = end.month() as f64 - start.month() as f64;
+    (360.0 * years + 30.0 * months + (d2 - d1) as f64) / 360.0
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Adjustment {
+    Unadjusted,
+    Following,
+    ModifiedFollowing, // following, unless that moves into the next month
+}
+
+impl Adjustment {
+    pub fn from_choice(choice: &str) -> Adjustment {
+        match choice {
+            "following" => Adjustment::Following,
+            "modified-following" => Adjustment::ModifiedFollowing,
+            _ => Adjustment::Unadjusted,
+        }
+    }
+}
+
+const MAX_ADJUSTMENT_DAYS: usize = 366;
+
+// Weekends plus the holidays of a calendar file
+#[derive(Default)]
+pub struct Calendar {
+    holidays: HashSet<NaiveDate>,
+}
+
+// Reads one holiday per line (the first CSV column); blank lines, '#' comments and a header
+// line are skipped
+fn read_calendar(path: &Path) -> Result<Calendar, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut holidays = HashSet::new();
+    let mut header_allowed = true;
+    for (index, line) in text.lines().enumerate() {
+        let cell = line.split(['#', ',', ';']).next().unwrap_or("").trim();
+        if cell.is_empty() {
+            continue;
+        }
+        match cashflows::parse_date(cell) {
+            Some(date) => {
+                header_allowed = false;
+                holidays.insert(date);
+            }
+            None if header_allowed => header_allowed = false,
+            None => return Err(format!("{} line {}: '{}' is not a date", path.display(), index + 1, cell)),
+        }
+    }
+    Ok(Calendar { holidays })
+}
+
+// 'none' or a readable calendar file
+pub fn load_calendar(text: &str) -> Result<Calendar, String> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("none") || text.is_empty() {
+        return Ok(Calendar::default());
+    }
+    read_calendar(Path::new(text))
+}
+
+pub fn validate_calendar(text: &str) -> Result<(), String> {
+    load_calendar(text).map(|_| ())
+}
+
+impl Calendar {
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    pub fn adjust(&self, date: NaiveDate, adjustment: Adjustment) -> Result<NaiveDate, String> {
+        if adjustment == Adjustment::Unadjusted {
+            return Ok(date);
+        }
+        let following = self.business_day_from(date, NaiveDate::succ_opt)?;
+        if adjustment == Adjustment::ModifiedFollowing && following.month() != date.month() {
+            return self.business_day_from(date, NaiveDate::pred_opt);
+        }
+        Ok(following)
+    }
+
+    // The first business day from `date` on, stepping a day at a time with `step`. A calendar
+    // that leaves no business day within a year is taken to be a mistake.
+    fn business_day_from(&self, date: NaiveDate, step: fn(&NaiveDate) -> Option<NaiveDate>) -> Result<NaiveDate, String> {
+        let mut day = date;
+        for _ in 0..MAX_ADJUSTMENT_DAYS {
+            if self.is_business_day(day) {
+                return Ok(day);
+            }
+            match step(&day) {
+                Some(next) => day = next,
+                None => break,
+            }
+        }
+        Err(format!("no business day within {} days of {}; check the holiday calendar", MAX_ADJUSTMENT_DAYS, date))
+    }
+}
+
+// Everything needed to turn cash flow dates into year fractions
+pub struct Conventions {
+    pub day_count: DayCount,
+    pub adjustment: Adjustment,
+    pub calendar: Calendar,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn fraction(day_count: DayCount, start: &str, end: &str) -> f64 {
+        day_count.year_fraction(date(start), date(end))
+    }
+
+    #[test]
+    fn thirty_360_month_ends() {
+        // US: the last of February counts as the 30th, and the 31st only when the start is 30 or 31
+        assert_close(fraction(DayCount::Thirty360Us, "2024-02-29", "2024-03-31"), 30.0 / 360.0, 1e-12);
+        assert_close(fraction(DayCount::Thirty360Us, "2023-02-28", "2024-02-29"), 1.0, 1e-12);
+        assert_close(fraction(DayCount::Thirty360Us, "2024-01-31", "2024-03-31"), 60.0 / 360.0, 1e-12);
+        assert_close(fraction(DayCount::Thirty360Us, "2024-01-15", "2024-03-31"), 76.0 / 360.0, 1e-12);
+        // European: every 31st is the 30th and February is left alone
+        assert_close(fraction(DayCount::Thirty360European, "2024-01-15", "2024-03-31"), 75.0 / 360.0, 1e-12);
+        assert_close(fraction(DayCount::Thirty360European, "2024-02-29", "2024-03-31"), 31.0 / 360.0, 1e-12);
+        assert_close(fraction(DayCount::Thirty360European, "2024-03-31", "2024-01-15"), -75.0 / 360.0, 1e-12);
+    }
+
+    #[test]
+    fn act_act_isda_splits_at_year_ends() {
+        // 92 days of 2023 and 91 days of the leap year 2024
+        assert_close(fraction(DayCount::ActActIsda, "2023-10-01", "2024-04-01"), 92.0 / 365.0 + 91.0 / 366.0, 1e-12);
+        assert_close(fraction(DayCount::ActActIsda, "2024-01-01", "2025-01-01"), 1.0, 1e-12);
+        assert_close(fraction(DayCount::Act365Fixed, "2024-01-01", "2025-01-01"), 366.0 / 365.0, 1e-12);
+    }
+
+    #[test]
+    fn business_day_adjustment() {
+        let calendar = Calendar { holidays: HashSet::from([date("2024-09-02")]) };
+        let adjust = |text: &str, adjustment| calendar.adjust(date(text), adjustment).unwrap();
+        // Saturday 31 August 2024, with Monday 2 September a holiday
+        assert_eq!(adjust("2024-08-31", Adjustment::Unadjusted), date("2024-08-31"));
+        assert_eq!(adjust("2024-08-31", Adjustment::Following), date("2024-09-03"));
+        assert_eq!(adjust("2024-08-31", Adjustment::ModifiedFollowing), date("2024-08-30"));
+        // Within the month the two agree
+        assert_eq!(adjust("2024-06-15", Adjustment::Following), date("2024-06-17"));
+        assert_eq!(adjust("2024-06-15", Adjustment::ModifiedFollowing), date("2024-06-17"));
+        assert_eq!(adjust("2024-06-17", Adjustment::ModifiedFollowing), date("2024-06-17"));
+    }
+
+    #[test]
+    fn calendar_without_business_days() {
+        let holidays = date("2024-01-01").iter_days().take(800).collect();
+        let calendar = Calendar { holidays };
+        let message = calendar.adjust(date("2024-06-03"), Adjustment::Following).unwrap_err();
+        assert!(message.contains("no business day within 366 days of 2024-06-03"), "{}", message);
+    }
+}
@@ -0,0 +1,231 @@
+// Interest rate sensitivities of a stream of (years, amount) flows discounted at a flat rate or
+// on a zero curve: Macaulay, modified and effective duration, convexity, DV01/PV01 and
+// key-rate durations
+
+pub const COMPOUNDINGS: &[&str] = &["annual", "semiannual", "quarterly", "monthly", "continuous"];
+
+// Key rate tenors in years with their output names
+pub const KEY_RATES: [(f64, &str, &str); 8] = [
+    (1.0, "krd_1y", "Key Rate Duration 1y"),
+    (2.0, "krd_2y", "Key Rate Duration 2y"),
+    (3.0, "krd_3y", "Key Rate Duration 3y"),
+    (5.0, "krd_5y", "Key Rate Duration 5y"),
+    (7.0, "krd_7y", "Key Rate Duration 7y"),
+    (10.0, "krd_10y", "Key Rate Duration 10y"),
+    (20.0, "krd_20y", "Key Rate Duration 20y"),
+    (30.0, "krd_30y", "Key Rate Duration 30y"),
+];
+
+const BASIS_POINT: f64 = 0.0001;
+
+#[derive(Clone, Copy)]
+pub enum Compounding {
+    Periodic(f64), // times a year
+    Continuous,
+}
+
+impl Compounding {
+    pub fn from_choice(choice: &str) -> Compounding {
+        match choice {
+            "semiannual" => Compounding::Periodic(2.0),
+            "quarterly" => Compounding::Periodic(4.0),
+            "monthly" => Compounding::Periodic(12.0),
+            "continuous" => Compounding::Continuous,
+            _ => Compounding::Periodic(1.0),
+        }
+    }
+
+    fn discount_factor(self, rate: f64, years: f64) -> f64 {
+        match self {
+            Compounding::Periodic(m) => (1.0 + rate / m).powf(-m * years),
+            Compounding::Continuous => (-rate * years).exp(),
+        }
+    }
+
+    // First and second derivatives of the discount factor by the rate, over the factor
+    fn sensitivities(self, rate: f64, years: f64) -> (f64, f64) {
+        match self {
+            Compounding::Periodic(m) => (-years / (1.0 + rate / m), years * (years + 1.0 / m) / (1.0 + rate / m).powi(2)),
+            Compounding::Continuous => (-years, years * years),
+        }
+    }
+}
+
+// Zero rates by tenor, interpolated linearly and held flat beyond the first and last tenor;
+// a single flat rate when there are no points
+pub struct Curve {
+    points: Vec<(f64, f64)>,
+    flat: f64,
+}
+
+impl Curve {
+    pub fn new(flat: f64, mut points: Vec<(f64, f64)>) -> Result<Curve, String> {
+        if let Some((tenor, _)) = points.iter().find(|(tenor, rate)| *tenor <= 0.0 || !tenor.is_finite() || !rate.is_finite()) {
+            return Err(format!("curve tenor {} must be a positive number of years", tenor));
+        }
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.dedup_by(|a, b| a.0 == b.0);
+        Ok(Curve { points, flat })
+    }
+
+    pub fn zero_rate(&self, years: f64) -> f64 {
+        let points = &self.points;
+        match points.len() {
+            0 => self.flat,
+            _ if years <= points[0].0 => points[0].1,
+            n if years >= points[n - 1].0 => points[n - 1].1,
+            _ => {
+                let i = points.partition_point(|(tenor, _)| *tenor <= years) - 1;
+                let ((t0, r0), (t1, r1)) = (points[i], points[i + 1]);
+                r0 + (r1 - r0) * (years - t0) / (t1 - t0)
+            }
+        }
+    }
+}
+
+pub struct Analytics {
+    pub present_value: f64,
+    pub macaulay: f64,
+    pub modified: f64,
+    pub convexity: f64,
+    pub effective_duration: f64,
+    pub effective_convexity: f64,
+    pub dv01: f64, // from the modified duration: -dPV/dy for one basis point
+    pub pv01: f64, // by repricing: PV now less PV after a one basis point parallel rise
+    pub key_rates: Vec<f64>, // one per KEY_RATES tenor
+}
+
+// Weight of a key rate shift at `years`: 1 at the tenor, falling linearly to 0 at the neighbouring
+// tenors, and 1 beyond the first and last tenor
+fn key_rate_weight(index: usize, years: f64) -> f64 {
+    let tenor = KEY_RATES[index].0;
+    let previous = index.checked_sub(1).map(|i| KEY_RATES[i].0);
+    let next = KEY_RATES.get(index + 1).map(|key| key.0);
+    if years <= tenor {
+        match previous {
+            Some(previous) if years > previous => (years - previous) / (tenor - previous),
+            Some(_) => 0.0,
+            None => 1.0,
+        }
+    } else {
+        match next {
+            Some(next) if years < next => (next - years) / (next - tenor),
+            Some(_) => 0.0,
+            None => 1.0,
+        }
+    }
+}
+
+fn present_value(flows: &[(f64, f64)], curve: &Curve, compounding: Compounding, shift: impl Fn(f64) -> f64) -> f64 {
+    flows.iter().map(|(years, amount)| amount * compounding.discount_factor(curve.zero_rate(*years) + shift(*years), *years)).sum()
+}
+
+pub fn analyse(flows: &[(f64, f64)], curve: &Curve, compounding: Compounding, shock: f64) -> Result<Analytics, String> {
+    if let Some((years, _)) = flows.iter().find(|(years, _)| *years < 0.0) {
+        return Err(format!("a flow {:.2} years before the valuation date cannot be discounted", -years));
+    }
+    if shock <= 0.0 {
+        return Err("shock must be greater than zero".to_string());
+    }
+    let pv = present_value(flows, curve, compounding, |_| 0.0);
+    if !pv.is_finite() {
+        return Err("a rate at or below -100% per compounding period cannot discount the flows".to_string());
+    }
+    if pv.abs() < 1e-12 {
+        return Err("the flows have no present value, so their durations are undefined".to_string());
+    }
+
+    let (mut macaulay, mut first, mut second) = (0.0, 0.0, 0.0);
+    for (years, amount) in flows {
+        let rate = curve.zero_rate(*years);
+        let value = amount * compounding.discount_factor(rate, *years);
+        let (slope, curvature) = compounding.sensitivities(rate, *years);
+        macaulay += years * value;
+        first += slope * value;
+        second += curvature * value;
+    }
+
+    let shifted = |size: f64| present_value(flows, curve, compounding, |_| size);
+    let (down, up) = (shifted(-shock), shifted(shock));
+    let key_rates = (0..KEY_RATES.len())
+        .map(|index| {
+            let bumped = |size: f64| present_value(flows, curve, compounding, |years| size * key_rate_weight(index, years));
+            (bumped(-shock) - bumped(shock)) / (2.0 * pv * shock)
+        })
+        .collect();
+    Ok(Analytics {
+        present_value: pv,
+        macaulay: macaulay / pv,
+        modified: -first / pv,
+        convexity: second / pv,
+        effective_duration: (down - up) / (2.0 * pv * shock),
+        effective_convexity: (down + up - 2.0 * pv) / (pv * shock * shock),
+        dv01: -first * BASIS_POINT,
+        pv01: pv - shifted(BASIS_POINT),
+        key_rates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    fn flat(rate: f64) -> Curve {
+        Curve::new(rate, Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn textbook_bond() {
+        // Five-year 10% annual coupon bond at a 10% yield
+        let flows = [(1.0, 10.0), (2.0, 10.0), (3.0, 10.0), (4.0, 10.0), (5.0, 110.0)];
+        let analytics = analyse(&flows, &flat(0.10), Compounding::Periodic(1.0), 0.0001).unwrap();
+        assert_close(analytics.present_value, 100.0, 1e-9);
+        assert_close(analytics.macaulay, 4.1699, 1e-4);
+        assert_close(analytics.modified, 3.7908, 1e-4);
+        assert_close(analytics.effective_duration, analytics.modified, 1e-6);
+        assert_close(analytics.effective_convexity, analytics.convexity, 1e-3);
+        assert_close(analytics.dv01, 0.037908, 1e-6);
+    }
+
+    #[test]
+    fn zero_coupon_duration_is_its_maturity() {
+        let analytics = analyse(&[(7.0, 100.0)], &flat(0.05), Compounding::Periodic(1.0), 0.001).unwrap();
+        assert_close(analytics.macaulay, 7.0, 1e-12);
+        assert_close(analytics.modified, 7.0 / 1.05, 1e-12);
+        assert_close(analytics.convexity, 7.0 * 8.0 / 1.05 / 1.05, 1e-12);
+        let continuous = analyse(&[(7.0, 100.0)], &flat(0.05), Compounding::Continuous, 0.001).unwrap();
+        assert_close(continuous.modified, 7.0, 1e-12);
+    }
+
+    #[test]
+    fn key_rate_durations_sum_to_effective_duration() {
+        let curve = Curve::new(0.0, vec![(1.0, 0.03), (5.0, 0.04), (30.0, 0.05)]).unwrap();
+        assert_close(curve.zero_rate(3.0), 0.035, 1e-12);
+        assert_close(curve.zero_rate(40.0), 0.05, 1e-12);
+        let flows: Vec<(f64, f64)> = (1..=40).map(|half| (half as f64 / 2.0, if half == 40 { 102.5 } else { 2.5 })).collect();
+        let analytics = analyse(&flows, &curve, Compounding::Periodic(2.0), 0.0001).unwrap();
+        assert_close(analytics.key_rates.iter().sum(), analytics.effective_duration, 1e-5);
+        // A 20-year bond has no exposure to the 30-year rate
+        assert_close(analytics.key_rates[7], 0.0, 1e-12);
+    }
+
+    #[test]
+    fn key_rate_weights_are_tents() {
+        assert_close(key_rate_weight(3, 6.0), 0.5, 1e-12); // halfway from 5y to 7y
+        assert_close(key_rate_weight(0, 0.5), 1.0, 1e-12);
+        assert_close(key_rate_weight(7, 40.0), 1.0, 1e-12);
+        assert_close(key_rate_weight(5, 3.0), 0.0, 1e-12);
+        for years in [0.25, 1.0, 4.0, 12.5, 29.0, 35.0] {
+            assert_close((0..KEY_RATES.len()).map(|index| key_rate_weight(index, years)).sum(), 1.0, 1e-12);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert!(analyse(&[(-1.0, 100.0)], &flat(0.05), Compounding::Continuous, 0.001).is_err());
+        assert!(analyse(&[(1.0, 100.0)], &flat(0.05), Compounding::Continuous, 0.0).is_err());
+        assert!(analyse(&[(1.0, 100.0), (1.0, -100.0)], &flat(0.05), Compounding::Continuous, 0.001).is_err());
+        assert!(Curve::new(0.05, vec![(0.0, 0.03)]).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value as Json};
+
+use crate::batch::cell_text;
+use crate::calculators;
+use crate::history::Record;
+
+// Export history records to CSV, JSON or a Markdown table per calculation
+
+pub fn export(records: &[Record], path: &Path) -> Result<(), String> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase());
+    let text = match extension.as_deref() {
+        Some("csv") => to_csv(records)?,
+        Some("json") => serde_json::to_string_pretty(records).map_err(|e| e.to_string())? + "\n",
+        Some("md") | Some("markdown") => to_markdown(records),
+        _ => return Err(format!("{}: expected a .csv, .json or .md file", path.display())),
+    };
+    fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn title(record: &Record) -> &str {
+    calculators::find(&record.calculator)
+        .map(|calc| calc.title)
+        .unwrap_or(&record.calculator)
+}
+
+// One row per record; input and output columns are the union over all records
+fn to_csv(records: &[Record]) -> Result<String, String> {
+    let mut input_columns: Vec<&str> = Vec::new();
+    let mut output_columns: Vec<&str> = Vec::new();
+    for record in records {
+        add_columns(&mut input_columns, &record.inputs);
+        add_columns(&mut output_columns, &record.outputs);
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut headers = vec!["id".to_string(), "timestamp".to_string(), "calculator".to_string()];
+    headers.extend(input_columns.iter().map(|column| format!("input.{}", column)));
+    headers.extend(output_columns.iter().map(|column| format!("output.{}", column)));
+    writer.write_record(&headers).map_err(|e| e.to_string())?;
+
+    for record in records {
+        let mut row = vec![record.id.to_string(), record.timestamp.clone(), record.calculator.clone()];
+        row.extend(input_columns.iter().map(|column| cell_text(record.inputs.get(*column))));
+        row.extend(output_columns.iter().map(|column| cell_text(record.outputs.get(*column))));
+        writer.write_record(&row).map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+fn add_columns<'a>(columns: &mut Vec<&'a str>, values: &'a Map<String, Json>) {
+    for key in values.keys() {
+        if !columns.contains(&key.as_str()) {
+            columns.push(key);
+        }
+    }
+}
+
+fn to_markdown(records: &[Record]) -> String {
+    let mut text = String::new();
+    for record in records {
+        text.push_str(&format!("### {} (#{}, {})\n\n", title(record), record.id, record.timestamp));
+        text.push_str("| Input | Value |\n|---|---:|\n");
+        for (key, value) in &record.inputs {
+            text.push_str(&format!("| {} | {} |\n", key, cell_text(Some(value)).replace(';', "; ")));
+        }
+        text.push_str("\n| Output | Value |\n|---|---:|\n");
+        for (key, value) in &record.outputs {
+            text.push_str(&format!("| {} | {} |\n", key, cell_text(Some(value))));
+        }
+        text.push('\n');
+    }
+    text
+}
@@ -0,0 +1,301 @@
+use crate::vars;
+
+// Arithmetic expressions typed at numeric prompts, e.g. `1250000 - 340000`, `0.21*(1-0.05)`,
+// `5%`, `1.2e6`, `3.5m`, `$1,234.56`, `$coe1 + 0.01` or `ans * 2`.
+//
+// expr    := term (('+' | '-') term)*
+// term    := unary (('*' | '/') unary)*
+// unary   := ('-' | '+') unary | power
+// power   := postfix ('^' unary)?
+// postfix := primary '%'*
+// primary := number suffix? | variable | '(' expr ')'
+
+pub fn evaluate(input: &str) -> Result<f64, String> {
+    parse(input, true)
+}
+
+// As `evaluate` for values read from files and the command line, where `ans` and saved
+// variables do not exist
+pub fn evaluate_constant(input: &str) -> Result<f64, String> {
+    parse(input, false)
+}
+
+fn parse(input: &str, variables: bool) -> Result<f64, String> {
+    let mut parser = Parser { chars: input.chars().collect(), pos: 0, variables };
+    parser.skip_whitespace();
+    if parser.at_end() {
+        return Err("expected a number".to_string());
+    }
+    let value = parser.expr()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(parser.error(&format!("unexpected '{}'", c)));
+    }
+    if !value.is_finite() {
+        return Err(format!("'{}' does not evaluate to a finite number", input));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    variables: bool, // whether `ans` and `$name` may be used
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Skips whitespace and consumes `c` if it is next
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at position {}", message, self.pos + 1)
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(self.error("division by zero"));
+                }
+                value /= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            return Ok(-self.unary()?);
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.postfix()?;
+        if self.eat('^') {
+            return Ok(base.powf(self.unary()?));
+        }
+        Ok(base)
+    }
+
+    fn postfix(&mut self) -> Result<f64, String> {
+        let mut value = self.primary()?;
+        while self.eat('%') {
+            value /= 100.0;
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> Result<f64, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expr()?;
+                if !self.eat(')') {
+                    return Err(self.error("missing ')'"));
+                }
+                Ok(value)
+            }
+            Some('$') => {
+                self.pos += 1;
+                match self.peek() {
+                    Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+                    Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                        let name = self.reference();
+                        self.variable(&name)
+                    }
+                    _ => Err(self.error("expected an amount or a variable name after '$'")),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let start = self.pos;
+                let name = self.reference();
+                if name == vars::ANS || name.starts_with("ans.") {
+                    return self.variable(&name);
+                }
+                self.pos = start;
+                Err(self.error(&format!("unknown name '{}' (variables start with '$')", name)))
+            }
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("expected a number")),
+        }
+    }
+
+    fn variable(&self, name: &str) -> Result<f64, String> {
+        if !self.variables {
+            return Err(format!("variables like '{}' can only be used at interactive prompts", name));
+        }
+        vars::lookup(name)
+    }
+
+    // Variable name with an optional `.output` part
+    fn reference(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    // Number with optional thousands separators, exponent and k/m/bn/t suffix
+    fn number(&mut self) -> Result<f64, String> {
+        let start = self.pos;
+        let mut text = String::new();
+        let mut group = 0; // integer digits since the start or the last thousands separator
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                if c.is_ascii_digit() && !text.contains('.') {
+                    group += 1;
+                }
+                text.push(c);
+            } else if c == ',' && self.chars.get(self.pos + 1).is_some_and(char::is_ascii_digit) {
+                // A thousands separator follows one to three digits, or a previous group, and
+                // is followed by exactly three digits
+                let following = self.chars[self.pos + 1..].iter().take_while(|c| c.is_ascii_digit()).count();
+                if text.contains('.') || !(1..=3).contains(&group) || following != 3 {
+                    return Err(self.error("misplaced thousands separator"));
+                }
+                group = 0;
+            } else {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        // Exponent, only when followed by digits so `e` is not mistaken for a suffix
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            let mut lookahead = self.pos + 1;
+            if matches!(self.chars.get(lookahead), Some('+') | Some('-')) {
+                lookahead += 1;
+            }
+            if self.chars.get(lookahead).is_some_and(char::is_ascii_digit) {
+                text.push('e');
+                self.pos += 1;
+                while let Some(c) = self.peek() {
+                    if !(c.is_ascii_digit() || (matches!(c, '+' | '-') && text.ends_with('e'))) {
+                        break;
+                    }
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        let value = text.parse::<f64>().map_err(|_| {
+            let position = start + 1;
+            format!("invalid number '{}' at position {}", text, position)
+        })?;
+
+        let suffix_start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let suffix: String = self.chars[suffix_start..self.pos].iter().collect();
+        let multiplier = match suffix.to_lowercase().as_str() {
+            "" => 1.0,
+            "k" => 1e3,
+            "m" | "mm" => 1e6,
+            "b" | "bn" => 1e9,
+            "t" => 1e12,
+            _ => {
+                self.pos = suffix_start;
+                return Err(self.error(&format!("unknown suffix '{}' (use k, m, bn or t)", suffix)));
+            }
+        };
+        Ok(value * multiplier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, evaluate_constant};
+
+    fn assert_evaluates(input: &str, expected: f64) {
+        let value = evaluate(input).unwrap_or_else(|e| panic!("'{}' failed: {}", input, e));
+        assert!((value - expected).abs() <= 1e-9 * expected.abs().max(1.0), "'{}' gave {} not {}", input, value, expected);
+    }
+
+    #[test]
+    fn suffixes_and_separators() {
+        assert_evaluates("1,234.5k", 1_234_500.0);
+        assert_evaluates("5%", 0.05);
+        assert_evaluates("1.2e6", 1.2e6);
+        assert_evaluates("2.5E-3", 0.0025);
+        assert_evaluates("3.5m", 3.5e6);
+        assert_evaluates("2bn", 2e9);
+        assert_evaluates("$1,234.56", 1234.56);
+        assert_evaluates("12,345,678", 12_345_678.0);
+        assert_evaluates("1,000+1", 1001.0);
+    }
+
+    #[test]
+    fn precedence() {
+        assert_evaluates("1250000 - 340000", 910_000.0);
+        assert_evaluates("0.21*(1-0.05)", 0.1995);
+        assert_evaluates("2 + 3 * 4", 14.0);
+        assert_evaluates("-2^2", -4.0);
+        assert_evaluates("2^3^2", 512.0);
+        assert_evaluates("50%%", 0.005);
+    }
+
+    #[test]
+    fn errors() {
+        for input in ["", "1/0", "3x", "(1", "1 2", "1e", "abc", "1,2", "1,23,4", "1234,567", "1,2345", "1.5,000", ",123"] {
+            assert!(evaluate(input).is_err(), "'{}' should not evaluate", input);
+        }
+    }
+
+    #[test]
+    fn constants_have_no_variables() {
+        assert_eq!(evaluate_constant("$1,234.56 * 2"), Ok(2469.12));
+        assert_eq!(evaluate_constant("5%"), Ok(0.05));
+        for input in ["ans", "ans.npv * 2", "$coe1 + 0.01"] {
+            let message = evaluate_constant(input).unwrap_err();
+            assert!(message.contains("only be used at interactive prompts"), "{}", message);
+        }
+    }
+}
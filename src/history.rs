@@ -0,0 +1,325 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as Json, json};
+
+use crate::calculators::{self, Calculator, Inputs, Output, summary, value_from_json};
+use crate::export::export;
+use crate::prompt;
+use crate::{clear_terminal, display_ascii_art, get_user_input, report_result};
+
+// Persistent history of results, stored as JSON lines in the user's data dir
+
+pub static HISTORY: Lazy<Mutex<History>> = Lazy::new(|| Mutex::new(History::load(history_path())));
+
+const PAGE_SIZE: usize = 10;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub id: u64,
+    pub timestamp: String,
+    pub calculator: String,
+    pub inputs: Map<String, Json>,
+    pub outputs: Map<String, Json>,
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerun_of: Option<u64>,
+}
+
+impl Record {
+    pub fn new(id: u64, calc: &Calculator, inputs: &Inputs, outputs: &[Output]) -> Record {
+        Record {
+            id,
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            calculator: calc.id.to_string(),
+            inputs: inputs.iter().map(|(field, value)| (field.key(), value.to_json())).collect(),
+            outputs: outputs.iter().map(|output| (output.name.to_string(), json!(output.value))).collect(),
+            summary: summary(outputs),
+            rerun_of: None,
+        }
+    }
+
+    // The recorded inputs as editable defaults for the record's calculator
+    fn defaults(&self, calc: &'static Calculator) -> Inputs {
+        let mut inputs = Inputs::new();
+        for field in calc.fields {
+            if let Some(value) = self.inputs.get(&field.key()).and_then(|json| value_from_json(field, json).ok()) {
+                inputs.push(field, value);
+            }
+        }
+        inputs
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.summary.to_lowercase().contains(&text)
+            || self.calculator.to_lowercase().contains(&text)
+            || self.timestamp.contains(&text)
+            || self
+                .inputs
+                .iter()
+                .any(|(key, value)| key.contains(&text) || value.to_string().to_lowercase().contains(&text))
+    }
+
+    fn inputs_text(&self) -> String {
+        self.inputs
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+pub struct History {
+    path: Option<PathBuf>,
+    records: Vec<Record>,
+    unreadable: Vec<String>, // lines that are not records, written back untouched on save
+    last_id: u64,            // the highest id ever given out, so deleted ids are never reused
+    session_start: u64,      // records with a higher id were added in this session
+}
+
+// Written as the first line when the file is rewritten, so the highest id survives deleting
+// the newest records
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Meta {
+    last_id: u64,
+}
+
+// IFCH_HISTORY_FILE overrides the default <data dir>/ifch/history.jsonl
+fn history_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("IFCH_HISTORY_FILE") {
+        return Some(PathBuf::from(path));
+    }
+    dirs::data_dir().map(|dir| dir.join("ifch").join("history.jsonl"))
+}
+
+impl History {
+    fn load(path: Option<PathBuf>) -> History {
+        let text = path.as_ref().and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default();
+        let mut records: Vec<Record> = Vec::new();
+        let mut unreadable = Vec::new();
+        let mut last_id = 0;
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(record) = serde_json::from_str::<Record>(line) {
+                last_id = last_id.max(record.id);
+                records.push(record);
+            } else if let Ok(meta) = serde_json::from_str::<Meta>(line) {
+                last_id = last_id.max(meta.last_id);
+            } else {
+                eprintln!("Warning: history line {} could not be read; it is kept in the file as it is", index + 1);
+                unreadable.push(line.to_string());
+            }
+        }
+        History { path, records, unreadable, last_id, session_start: last_id }
+    }
+
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    pub fn last(&self) -> Option<&Record> {
+        self.records.last()
+    }
+
+    pub fn session_records(&self) -> Vec<Record> {
+        self.records
+            .iter()
+            .filter(|record| record.id > self.session_start)
+            .cloned()
+            .collect()
+    }
+
+    pub fn add(&mut self, calc: &Calculator, inputs: &Inputs, outputs: &[Output], rerun_of: Option<u64>) -> u64 {
+        self.last_id += 1;
+        let record = Record {
+            rerun_of,
+            ..Record::new(self.last_id, calc, inputs, outputs)
+        };
+        if let Err(e) = self.append(&record) {
+            eprintln!("Could not save history: {}", e);
+        }
+        let id = record.id;
+        self.records.push(record);
+        id
+    }
+
+    pub fn delete(&mut self, id: u64) -> bool {
+        let before = self.records.len();
+        self.records.retain(|record| record.id != id);
+        if self.records.len() == before {
+            return false;
+        }
+        if let Err(e) = self.save() {
+            eprintln!("Could not save history: {}", e);
+        }
+        true
+    }
+
+    fn append(&self, record: &Record) -> io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)
+    }
+
+    // Rewrites the file through a temporary file renamed into place, so a crash part way
+    // through leaves the old history intact
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let mut text = serde_json::to_string(&Meta { last_id: self.last_id })?;
+        text.push('\n');
+        for line in &self.unreadable {
+            text.push_str(line);
+            text.push('\n');
+        }
+        for record in &self.records {
+            text.push_str(&serde_json::to_string(record)?);
+            text.push('\n');
+        }
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(&temporary, path)
+    }
+}
+
+// History screen with paging, filtering by calculator, text search and deletion
+pub fn display_history() {
+    let mut calculator_filter: Option<String> = None;
+    let mut search: Option<String> = None;
+    let mut page = 0;
+
+    loop {
+        let history = HISTORY.lock().unwrap();
+        // Newest first
+        let matching: Vec<&Record> = history
+            .records()
+            .iter()
+            .rev()
+            .filter(|record| calculator_filter.as_ref().is_none_or(|calc| &record.calculator == calc))
+            .filter(|record| search.as_ref().is_none_or(|text| record.matches(text)))
+            .collect();
+        let pages = matching.len().div_ceil(PAGE_SIZE).max(1);
+        page = page.min(pages - 1);
+
+        clear_terminal();
+        display_ascii_art();
+        println!("\nHistory of Results (page {} of {}, {} entries):", page + 1, pages, matching.len());
+        if let Some(calc) = &calculator_filter {
+            println!("Filtered by calculator: {}", calc);
+        }
+        if let Some(text) = &search {
+            println!("Search: \"{}\"", text);
+        }
+        println!();
+        for record in matching.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+            match record.rerun_of {
+                Some(original) => println!("#{:<4} {}  {}  (re-run of #{})", record.id, record.timestamp, record.summary, original),
+                None => println!("#{:<4} {}  {}", record.id, record.timestamp, record.summary),
+            }
+            println!("      {}: {}", record.calculator, record.inputs_text());
+        }
+        let shown: Vec<Record> = matching.iter().rev().map(|record| (*record).clone()).collect();
+        drop(history);
+
+        println!("--------------");
+        println!("n. Next page");
+        println!("p. Previous page");
+        println!("f. Filter by calculator");
+        println!("s. Search");
+        println!("c. Clear filter and search");
+        println!("r. Re-run entry with edited inputs");
+        println!("d. Delete entry");
+        println!("e. Export shown entries (CSV, JSON or Markdown)");
+        println!("b. Back");
+        print!("Enter your choice: ");
+        io::stdout().flush().unwrap();
+
+        match get_user_input().trim() {
+            "n" => page += 1,
+            "p" => page = page.saturating_sub(1),
+            "f" => {
+                println!("Enter calculator id (e.g. quick_ratio): ");
+                let calc = get_user_input().trim().to_string();
+                calculator_filter = (!calc.is_empty()).then_some(calc);
+                page = 0;
+            }
+            "s" => {
+                println!("Enter search text: ");
+                let text = get_user_input().trim().to_string();
+                search = (!text.is_empty()).then_some(text);
+                page = 0;
+            }
+            "c" => {
+                calculator_filter = None;
+                search = None;
+                page = 0;
+            }
+            "r" => rerun_entry(),
+            "d" => delete_entry(),
+            "e" => export_entries(&shown),
+            "b" => break,
+            _ => {}
+        }
+    }
+}
+
+fn rerun_entry() {
+    println!("Enter entry number to re-run: ");
+    let Ok(id) = get_user_input().trim().trim_start_matches('#').parse::<u64>() else {
+        return;
+    };
+    let record = HISTORY.lock().unwrap().records().iter().find(|record| record.id == id).cloned();
+    let Some(record) = record else {
+        println!("No entry #{}.", id);
+        return;
+    };
+    let Some(calc) = calculators::find(&record.calculator) else {
+        println!("Entry #{} was made with an unknown calculator '{}'.", id, record.calculator);
+        return;
+    };
+
+    println!("\nRe-running #{} {} - press Enter to keep a previous value.", id, calc.title);
+    // Cancelling simply returns to the history screen
+    if let Ok(inputs) = prompt::prompt_inputs(calc, &record.defaults(calc)) {
+        report_result(calc, &inputs, Some(id));
+    }
+    println!("Press Enter to continue...");
+    let _ = get_user_input();
+}
+
+fn delete_entry() {
+    println!("Enter entry number to delete: ");
+    let Ok(id) = get_user_input().trim().trim_start_matches('#').parse::<u64>() else {
+        return;
+    };
+    println!("Delete entry #{}? (y/n): ", id);
+    if get_user_input().trim().eq_ignore_ascii_case("y") {
+        HISTORY.lock().unwrap().delete(id);
+    }
+}
+
+fn export_entries(records: &[Record]) {
+    println!("Enter file to export to (.csv, .json or .md): ");
+    let path = get_user_input().trim().to_string();
+    if path.is_empty() {
+        return;
+    }
+    match export(records, Path::new(&path)) {
+        Ok(()) => println!("Exported {} entries to {}", records.len(), path),
+        Err(message) => println!("Export failed: {}", message),
+    }
+    println!("Press Enter to continue...");
+    let _ = get_user_input();
+}
@@ -0,0 +1,286 @@
+use chrono::NaiveDate;
+
+use crate::cashflows;
+use crate::daycount::Conventions;
+
+// Internal rates of return found by scanning the NPV profile for sign changes and refining
+// each bracket with Brent's method, so every root in the scanned range is reported
+
+#[derive(Clone, Copy)]
+pub struct Root {
+    pub rate: f64,
+    pub iterations: u32,
+    pub residual: f64, // NPV at the reported rate
+}
+
+const MAX_ITERATIONS: u32 = 200;
+
+// Brent's method on [a, b], where f(a) and f(b) have opposite signs
+pub fn brent(f: impl Fn(f64) -> f64, mut a: f64, mut b: f64) -> Root {
+    let (mut fa, mut fb) = (f(a), f(b));
+    if fa.abs() < fb.abs() {
+        std::mem::swap(&mut a, &mut b);
+        std::mem::swap(&mut fa, &mut fb);
+    }
+    let (mut c, mut fc) = (a, fa);
+    let mut d = b - a;
+    let mut bisected = true;
+    for iteration in 1..=MAX_ITERATIONS {
+        if fb == 0.0 || (b - a).abs() < 1e-14 * (1.0 + b.abs()) {
+            return Root { rate: b, iterations: iteration, residual: fb };
+        }
+        let mut s = if fa != fc && fb != fc {
+            // inverse quadratic interpolation
+            a * fb * fc / ((fa - fb) * (fa - fc)) + b * fa * fc / ((fb - fa) * (fb - fc)) + c * fa * fb / ((fc - fa) * (fc - fb))
+        } else {
+            b - fb * (b - a) / (fb - fa)
+        };
+        let between = (s - (3.0 * a + b) / 4.0) * (s - b) < 0.0;
+        let slow = if bisected { (s - b).abs() >= (b - c).abs() / 2.0 } else { (s - b).abs() >= (c - d).abs() / 2.0 };
+        bisected = !between || slow;
+        if bisected {
+            s = 0.5 * (a + b);
+        }
+        let fs = f(s);
+        d = c;
+        c = b;
+        fc = fb;
+        if fa * fs < 0.0 {
+            b = s;
+            fb = fs;
+        } else {
+            a = s;
+            fa = fs;
+        }
+        if fa.abs() < fb.abs() {
+            std::mem::swap(&mut a, &mut b);
+            std::mem::swap(&mut fa, &mut fb);
+        }
+    }
+    Root { rate: b, iterations: MAX_ITERATIONS, residual: fb }
+}
+
+// Newton's method from `guess`; None when it leaves the (-1, inf) domain or does not settle
+pub fn newton(f: impl Fn(f64) -> f64, derivative: impl Fn(f64) -> f64, guess: f64, tolerance: f64) -> Option<Root> {
+    let mut rate = guess;
+    for iteration in 1..=MAX_ITERATIONS {
+        let (value, slope) = (f(rate), derivative(rate));
+        if slope == 0.0 || !slope.is_finite() {
+            return None;
+        }
+        let next = rate - value / slope;
+        if !next.is_finite() || next <= -1.0 {
+            return None;
+        }
+        if (next - rate).abs() < 1e-12 * (1.0 + rate.abs()) {
+            let residual = f(next);
+            return (residual.abs() <= tolerance).then_some(Root { rate: next, iterations: iteration, residual });
+        }
+        rate = next;
+    }
+    None
+}
+
+// Rates scanned for sign changes: fine steps from -99% to 100%, coarser up to 1000%
+fn scan_grid() -> Vec<f64> {
+    let mut grid: Vec<f64> = (0..=796).map(|i| -0.99 + 0.0025 * i as f64).collect();
+    grid.extend((1..=180).map(|i| 1.0 + 0.05 * i as f64));
+    grid
+}
+
+// Every rate in the scanned range at which `npv` changes sign, lowest first
+pub fn find_roots(npv: impl Fn(f64) -> f64) -> Vec<Root> {
+    let grid = scan_grid();
+    let values: Vec<f64> = grid.iter().map(|rate| npv(*rate)).collect();
+    let mut roots = Vec::new();
+    for i in 0..grid.len() - 1 {
+        let (a, b) = (values[i], values[i + 1]);
+        if !a.is_finite() || !b.is_finite() {
+            continue;
+        }
+        if a == 0.0 {
+            roots.push(Root { rate: grid[i], iterations: 0, residual: 0.0 });
+        } else if a * b < 0.0 {
+            roots.push(brent(&npv, grid[i], grid[i + 1]));
+        }
+    }
+    roots
+}
+
+// Number of sign changes in a cash flow sequence, ignoring zeros; more than one means the
+// flows are non-conventional and may have several IRRs
+pub fn sign_changes(amounts: &[f64]) -> usize {
+    let signs: Vec<bool> = amounts.iter().filter(|amount| **amount != 0.0).map(|amount| *amount > 0.0).collect();
+    signs.windows(2).filter(|pair| pair[0] != pair[1]).count()
+}
+
+// Periodic flows: `flows[t]` is paid or received at the end of period t, with t = 0 today
+
+pub fn npv(flows: &[f64], rate: f64) -> f64 {
+    flows.iter().enumerate().map(|(t, flow)| flow / (1.0 + rate).powi(t as i32)).sum()
+}
+
+// Modified IRR: negative flows are financed at `finance_rate`, positive ones reinvested at
+// `reinvestment_rate`
+pub fn mirr(flows: &[f64], finance_rate: f64, reinvestment_rate: f64) -> Option<f64> {
+    let n = flows.len() - 1;
+    if n == 0 {
+        return None;
+    }
+    let outflows: f64 = flows
+        .iter()
+        .enumerate()
+        .filter(|(_, flow)| **flow < 0.0)
+        .map(|(t, flow)| flow / (1.0 + finance_rate).powi(t as i32))
+        .sum();
+    let inflows: f64 = flows
+        .iter()
+        .enumerate()
+        .filter(|(_, flow)| **flow > 0.0)
+        .map(|(t, flow)| flow * (1.0 + reinvestment_rate).powi((n - t) as i32))
+        .sum();
+    if outflows >= 0.0 || inflows <= 0.0 {
+        return None;
+    }
+    Some((inflows / -outflows).powf(1.0 / n as f64) - 1.0)
+}
+
+// Periods until the cumulative flows turn non-negative, interpolating within the period
+pub fn payback(flows: &[f64]) -> Option<f64> {
+    let mut cumulative = 0.0;
+    for (t, flow) in flows.iter().enumerate() {
+        let before = cumulative;
+        cumulative += flow;
+        if cumulative >= 0.0 && before < 0.0 {
+            return Some(t as f64 - 1.0 + -before / flow);
+        }
+    }
+    None
+}
+
+pub fn discounted_payback(flows: &[f64], rate: f64) -> Option<f64> {
+    let discounted: Vec<f64> = flows.iter().enumerate().map(|(t, flow)| flow / (1.0 + rate).powi(t as i32)).collect();
+    payback(&discounted)
+}
+
+// Present value of the later flows per unit of initial investment
+pub fn profitability_index(flows: &[f64], rate: f64) -> Option<f64> {
+    let investment = -flows[0];
+    if investment <= 0.0 {
+        return None;
+    }
+    Some((npv(flows, rate) + investment) / investment)
+}
+
+// Dated flows as (amount, years after the first flow) under a day-count convention, with each
+// date first moved to a business day; the first flow is the base date, as in ifch's `xnpv`
+
+fn parse_dates(flows: &[(f64, &str)]) -> Result<Vec<NaiveDate>, String> {
+    flows
+        .iter()
+        .map(|(_, date)| cashflows::parse_iso_date(date).ok_or_else(|| format!("'{}' is not a valid YYYY-MM-DD date", date)))
+        .collect()
+}
+
+pub fn adjusted_dates(flows: &[(f64, &str)], conventions: &Conventions) -> Result<Vec<NaiveDate>, String> {
+    parse_dates(flows)?.into_iter().map(|date| conventions.calendar.adjust(date, conventions.adjustment)).collect()
+}
+
+pub fn dated_flows(flows: &[(f64, &str)], conventions: &Conventions) -> Result<Vec<(f64, f64)>, String> {
+    let dates = adjusted_dates(flows, conventions)?;
+    Ok(flows.iter().zip(&dates).map(|((amount, _), date)| (*amount, conventions.day_count.year_fraction(dates[0], *date))).collect())
+}
+
+// Rejects lists that cannot have an IRR or whose dates are out of order
+pub fn check_irr_flows(flows: &[(f64, &str)]) -> Result<(), String> {
+    if !flows.iter().any(|(amount, _)| *amount > 0.0) || !flows.iter().any(|(amount, _)| *amount < 0.0) {
+        return Err("no IRR exists: the cash flows need at least one positive and one negative amount".to_string());
+    }
+    if let Some(pair) = parse_dates(flows)?.windows(2).find(|pair| pair[1] < pair[0]) {
+        return Err(format!("cash flow dated {} is listed after one dated {}; list the flows in date order", pair[1], pair[0]));
+    }
+    Ok(())
+}
+
+pub fn xnpv(flows: &[(f64, f64)], rate: f64) -> f64 {
+    flows.iter().map(|(amount, years)| amount / (1.0 + rate).powf(*years)).sum()
+}
+
+fn xnpv_derivative(flows: &[(f64, f64)], rate: f64) -> f64 {
+    flows.iter().map(|(amount, years)| -years * amount / (1.0 + rate).powf(years + 1.0)).sum()
+}
+
+pub enum Method {
+    Newton,
+    Brent, // on a bracket from the NPV profile scan
+}
+
+pub struct Xirr {
+    pub root: Root,
+    pub method: Method,
+    pub roots: Vec<Root>, // every root of the NPV profile scan
+}
+
+// Newton's method from 10% as in ifch's `xirr`, falling back to Brent's method on the first
+// bracket of the NPV profile scan when Newton fails; None when the NPV never crosses zero
+pub fn xirr(flows: &[(f64, f64)]) -> Option<Xirr> {
+    let tolerance = 1e-9 * flows.iter().map(|(amount, _)| amount.abs()).sum::<f64>();
+    let npv = |rate: f64| xnpv(flows, rate);
+    let roots = find_roots(npv);
+    if let Some(root) = newton(npv, |rate| xnpv_derivative(flows, rate), 0.1, tolerance) {
+        return Some(Xirr { root, method: Method::Newton, roots });
+    }
+    let root = *roots.first()?;
+    Some(Xirr { root, method: Method::Brent, roots })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daycount::{Adjustment, Calendar, DayCount};
+    use crate::testing::assert_close;
+
+    fn conventions() -> Conventions {
+        Conventions { day_count: DayCount::from_choice("act/365f"), adjustment: Adjustment::from_choice("none"), calendar: Calendar::default() }
+    }
+
+    #[test]
+    fn xirr_matches_excel() {
+        let flows = [(-10000.0, "2008-01-01"), (2750.0, "2008-03-01"), (4250.0, "2008-10-30"), (3250.0, "2009-02-15"), (2750.0, "2009-04-01")];
+        let solution = xirr(&dated_flows(&flows, &conventions()).unwrap()).unwrap();
+        assert_close(solution.root.rate, 0.373362535, 1e-8);
+        assert_eq!(solution.roots.len(), 1);
+    }
+
+    #[test]
+    fn multiple_roots() {
+        // -100 + 230/(1+r) - 132/(1+r)^2 is zero at 10% and 20%
+        let rates: Vec<f64> = find_roots(|r| npv(&[-100.0, 230.0, -132.0], r)).iter().map(|root| root.rate).collect();
+        assert_eq!(rates.len(), 2);
+        assert_close(rates[0], 0.10, 1e-9);
+        assert_close(rates[1], 0.20, 1e-9);
+        assert_eq!(sign_changes(&[-100.0, 230.0, -132.0]), 2);
+
+        let solution = xirr(&[(-100.0, 0.0), (230.0, 1.0), (-132.0, 2.0)]).unwrap();
+        assert_eq!(solution.roots.len(), 2);
+        assert!((solution.root.rate - 0.10).abs() < 1e-9 || (solution.root.rate - 0.20).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flows_of_one_sign_have_no_irr() {
+        let positive = [(100.0, "2024-01-01"), (50.0, "2025-01-01")];
+        let negative = [(-100.0, "2024-01-01"), (-50.0, "2025-01-01")];
+        assert!(check_irr_flows(&positive).unwrap_err().starts_with("no IRR exists"));
+        assert!(check_irr_flows(&negative).unwrap_err().starts_with("no IRR exists"));
+        assert!(xirr(&dated_flows(&positive, &conventions()).unwrap()).is_none());
+        assert!(find_roots(|r| npv(&[-100.0, -50.0], r)).is_empty());
+    }
+
+    #[test]
+    fn unsorted_dates_are_rejected() {
+        let flows = [(-1000.0, "2024-01-01"), (600.0, "2025-12-31"), (600.0, "2024-12-31")];
+        let message = check_irr_flows(&flows).unwrap_err();
+        assert!(message.contains("2024-12-31 is listed after one dated 2025-12-31"), "{}", message);
+        assert!(check_irr_flows(&[(-1000.0, "2024-01-01"), (600.0, "2024-13-01")]).is_err());
+    }
+}
@@ -1,20 +1,45 @@
-use clap::{Arg, Command};
+mod amortization;
+mod batch;
+mod bond;
+mod calculators;
+mod cashflows;
+mod cli;
+mod daycount;
+mod duration;
+mod export;
+mod expr;
+mod history;
+mod irr;
+mod options;
+mod prompt;
+mod strategy;
+mod surface;
+#[cfg(test)]
+mod testing;
+mod tvm;
+mod vars;
+
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::process::Command as ProcessCommand;
-use ifch::*;
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
+use calculators::*;
+use history::{HISTORY, display_history};
 
 // Define global variables using once_cell
-static LAST_RESULT: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
-static HISTORY: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static EXPORT_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+static LAST_RESULT: Lazy<Mutex<String>> = Lazy::new(|| {
+    let history = HISTORY.lock().unwrap();
+    Mutex::new(history.last().map(|record| format!("Last: {}", record.summary)).unwrap_or_default())
+});
 
 // Helper Functions
 
 fn clear_terminal() {
     if cfg!(target_os = "windows") {
         ProcessCommand::new("cmd")
-            .args(&["/C", "cls"])
+            .args(["/C", "cls"])
             .status()
             .unwrap();
     } else {
@@ -45,26 +70,63 @@ ___) (___| )      | (____/\| )   ( |
 
 fn get_user_input() -> String {
     let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
+    // Nothing more can be entered once stdin is closed
+    if io::stdin().read_line(&mut input).unwrap() == 0 {
+        quit();
+    }
     input
 }
 
-// Function to display history
-fn display_history() {
-    clear_terminal();
-    display_ascii_art();
-    println!("\nHistory of Results:");
-    let history = HISTORY.lock().unwrap();
-    for result in history.iter() {
-        println!("{}", result);
+// Writes this session's results to the --export file, if one was given, and exits
+fn quit() -> ! {
+    if let Some(path) = EXPORT_PATH.lock().unwrap().as_ref() {
+        let records = HISTORY.lock().unwrap().session_records();
+        match export::export(&records, path) {
+            Ok(()) => println!("Exported {} results to {}", records.len(), path.display()),
+            Err(message) => eprintln!("Export failed: {}", message),
+        }
+    }
+    std::process::exit(0);
+}
+
+// Prompts for every input of a calculator, runs it and records the result.
+// Cancelling at a prompt goes back to `menu`.
+fn run_calculator(calc: &'static Calculator, menu: fn()) {
+    match prompt::prompt_inputs(calc, &Inputs::new()) {
+        Ok(inputs) => report_result(calc, &inputs, None),
+        Err(prompt::Cancelled) => menu(),
+    }
+}
+
+// Runs a calculator on collected inputs, prints the outputs and adds them to the history
+fn report_result(calc: &'static Calculator, inputs: &Inputs, rerun_of: Option<u64>) {
+    match evaluate_with_report(calc, inputs) {
+        Ok(evaluation) => {
+            let outputs = &evaluation.outputs;
+            print!("{}", format_result(&evaluation));
+            if rerun_of.is_some() {
+                evaluation.files.iter().for_each(|(path, _)| println!("Not writing {} on a re-run", path.display()));
+            } else if let Err(message) = write_files(&evaluation) {
+                println!("Export failed: {}", message);
+            }
+            *LAST_RESULT.lock().unwrap() = format!("Last: {}", summary(outputs));
+            HISTORY.lock().unwrap().add(calc, inputs, outputs, rerun_of);
+            vars::set_last(outputs);
+            vars::offer_save(outputs);
+        }
+        Err(message) => println!("{}", message),
     }
-    println!("\nPress Enter to return to the main menu...");
-    let _ = get_user_input();
 }
 
 // Main part of the file
 
 fn main() {
+    let matches = cli::build_cli().get_matches();
+    if matches.subcommand().is_some() {
+        std::process::exit(cli::run(&matches));
+    }
+    *EXPORT_PATH.lock().unwrap() = matches.get_one::<PathBuf>("export").cloned();
+
     clear_terminal();
     display_ascii_art();
     loop {
@@ -78,8 +140,10 @@ fn main() {
             "4" => wacc_menu(),
             "5" => valuation_menu(),
             "6" => options_menu(),
+            "7" => bonds_menu(),
             "H" | "h" => display_history(),
-            "q" => break,
+            "V" | "v" => vars::display_variables(),
+            "q" => quit(),
             _ => println!("Invalid option, please try again."),
         }
     }
@@ -95,8 +159,10 @@ fn main_menu() {
     println!("4. WACC");
     println!("5. Valuation");
     println!("6. Options");
+    println!("7. Bonds");
     println!("--------------");
     println!("h. History");
+    println!("v. Variables");
     println!("q. Quit");
     print!("Enter your choice: ");
     io::stdout().flush().unwrap();
@@ -125,7 +191,7 @@ fn ratios_menu() {
         "4" => activity_ratios_menu(),
         "5" => valuation_ratios_menu(),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
@@ -146,82 +212,16 @@ fn liquidity_ratios_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_quick_ratio(),
-        "2" => run_acid_test_ratio(),
-        "3" => run_cash_ratio(),
-        "4" => run_current_ratio(),
+        "1" => run_calculator(&QUICK_RATIO, liquidity_ratios_menu),
+        "2" => run_calculator(&ACID_TEST_RATIO, liquidity_ratios_menu),
+        "3" => run_calculator(&CASH_RATIO, liquidity_ratios_menu),
+        "4" => run_calculator(&CURRENT_RATIO, liquidity_ratios_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_quick_ratio() {
-    println!("Enter current assets: ");
-    let current_assets = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter inventory: ");
-    let inventory = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter current liabilities: ");
-    let current_liabilities = get_user_input().trim().parse::<f64>().unwrap();
-
-    let ratio = quick_r(current_assets, inventory, current_liabilities);
-    println!("Quick Ratio: {:.2}", ratio);
-    {
-        let result = format!("Quick Ratio = {:.2}", ratio);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_acid_test_ratio() {
-    println!("Enter cash: ");
-    let cash = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter inventory: ");
-    let inventory = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter accounts receivable: ");
-    let accounts_receivable = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter current liabilities: ");
-    let current_liabilities = get_user_input().trim().parse::<f64>().unwrap();
-
-    let ratio = acid_r(cash, inventory, accounts_receivable, current_liabilities);
-    println!("Acid Test Ratio: {:.2}", ratio);
-    {
-        let result = format!("Acid Test Ratio = {:.2}", ratio);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_cash_ratio() {
-    println!("Enter cash and equivalents: ");
-    let cash_and_equivalents = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter current liabilities: ");
-    let current_liabilities = get_user_input().trim().parse::<f64>().unwrap();
-
-    let ratio = cash_r(cash_and_equivalents, current_liabilities);
-    println!("Cash Ratio: {:.2}", ratio);
-    {
-        let result = format!("Cash Ratio = {:.2}", ratio);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_current_ratio() {
-    println!("Enter current assets: ");
-    let current_assets = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter current liabilities: ");
-    let current_liabilities = get_user_input().trim().parse::<f64>().unwrap();
-
-    let ratio = current_r(current_assets, current_liabilities);
-    println!("Current Ratio: {:.2}", ratio);
-    {
-        let result = format!("Current Ratio = {:.2}", ratio);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
 fn profitability_ratios_menu() {
     clear_terminal();
     display_ascii_art();
@@ -239,92 +239,17 @@ fn profitability_ratios_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_gross_margin(),
-        "2" => run_operating_margin(),
-        "3" => run_net_margin(),
-        "4" => run_return_on_assets(),
-        "5" => run_return_on_equity(),
+        "1" => run_calculator(&GROSS_MARGIN, profitability_ratios_menu),
+        "2" => run_calculator(&OPERATING_MARGIN, profitability_ratios_menu),
+        "3" => run_calculator(&NET_MARGIN, profitability_ratios_menu),
+        "4" => run_calculator(&RETURN_ON_ASSETS, profitability_ratios_menu),
+        "5" => run_calculator(&RETURN_ON_EQUITY, profitability_ratios_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_gross_margin() {
-    println!("Enter gross profit: ");
-    let gross_profit = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter revenue: ");
-    let revenue = get_user_input().trim().parse::<f64>().unwrap();
-
-    let margin = gross_m(gross_profit, revenue);
-    println!("Gross Margin: {:.2}", margin);
-    {
-        let result = format!("Gross Margin = {:.2}", margin);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_operating_margin() {
-    println!("Enter operating income: ");
-    let operating_income = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter revenue: ");
-    let revenue = get_user_input().trim().parse::<f64>().unwrap();
-
-    let margin = operating_m(operating_income, revenue);
-    println!("Operating Margin: {:.2}", margin);
-    {
-        let result = format!("Operating Margin = {:.2}", margin);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_net_margin() {
-    println!("Enter net income: ");
-    let net_income = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter revenue: ");
-    let revenue = get_user_input().trim().parse::<f64>().unwrap();
-
-    let margin = net_m(net_income, revenue);
-    println!("Net Margin: {:.2}", margin);
-    {
-        let result = format!("Net Margin = {:.2}", margin);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_return_on_assets() {
-    println!("Enter net income: ");
-    let net_income = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter total assets: ");
-    let total_assets = get_user_input().trim().parse::<f64>().unwrap();
-
-    let roa = r_o_a(net_income, total_assets);
-    println!("Return on Assets (ROA): {:.2}", roa);
-    {
-        let result = format!("ROA = {:.2}", roa);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_return_on_equity() {
-    println!("Enter net income: ");
-    let net_income = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter shareholders' equity: ");
-    let shareholders_equity = get_user_input().trim().parse::<f64>().unwrap();
-
-    let roe = r_o_e(net_income, shareholders_equity);
-    println!("Return on Equity (ROE): {:.2}", roe);
-    {
-        let result = format!("ROE = {:.2}", roe);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
 fn leverage_ratios_menu() {
     clear_terminal();
     display_ascii_art();
@@ -340,60 +265,15 @@ fn leverage_ratios_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_debt_to_equity_ratio(),
-        "2" => run_debt_ratio(),
-        "3" => run_ebit_interest_coverage(),
+        "1" => run_calculator(&DEBT_TO_EQUITY_RATIO, leverage_ratios_menu),
+        "2" => run_calculator(&DEBT_RATIO, leverage_ratios_menu),
+        "3" => run_calculator(&EBIT_INTEREST_COVERAGE, leverage_ratios_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_debt_to_equity_ratio() {
-    println!("Enter total debt: ");
-    let total_debt = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter shareholders' equity: ");
-    let shareholders_equity = get_user_input().trim().parse::<f64>().unwrap();
-
-    let dte = d_t_e(total_debt, shareholders_equity);
-    println!("Debt to Equity Ratio: {:.2}", dte);
-    {
-        let result = format!("Debt to Equity Ratio = {:.2}", dte);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_debt_ratio() {
-    println!("Enter total debt: ");
-    let total_debt = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter total assets: ");
-    let total_assets = get_user_input().trim().parse::<f64>().unwrap();
-
-    let dr = d_r(total_debt, total_assets);
-    println!("Debt Ratio: {:.2}", dr);
-    {
-        let result = format!("Debt Ratio = {:.2}", dr);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_ebit_interest_coverage() {
-    println!("Enter EBIT: ");
-    let ebit = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter interest expense: ");
-    let interest_expense = get_user_input().trim().parse::<f64>().unwrap();
-
-    let ebit_ic = ebit_i_c(ebit, interest_expense);
-    println!("EBIT Interest Coverage Ratio: {:.2}", ebit_ic);
-    {
-        let result = format!("EBIT Interest Coverage Ratio = {:.2}", ebit_ic);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
 fn activity_ratios_menu() {
     clear_terminal();
     display_ascii_art();
@@ -409,60 +289,15 @@ fn activity_ratios_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_inventory_turnover(),
-        "2" => run_receivables_turnover(),
-        "3" => run_asset_turnover(),
+        "1" => run_calculator(&INVENTORY_TURNOVER, activity_ratios_menu),
+        "2" => run_calculator(&RECEIVABLES_TURNOVER, activity_ratios_menu),
+        "3" => run_calculator(&ASSET_TURNOVER, activity_ratios_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_inventory_turnover() {
-    println!("Enter cost of goods sold: ");
-    let cost_of_goods_sold = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter average inventory: ");
-    let average_inventory = get_user_input().trim().parse::<f64>().unwrap();
-
-    let inv_turnover = inv_t(cost_of_goods_sold, average_inventory);
-    println!("Inventory Turnover: {:.2}", inv_turnover);
-    {
-        let result = format!("Inventory Turnover = {:.2}", inv_turnover);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_receivables_turnover() {
-    println!("Enter revenue: ");
-    let revenue = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter average accounts receivable: ");
-    let average_accounts_receivable = get_user_input().trim().parse::<f64>().unwrap();
-
-    let rec_turnover = rec_t(revenue, average_accounts_receivable);
-    println!("Receivables Turnover: {:.2}", rec_turnover);
-    {
-        let result = format!("Receivables Turnover = {:.2}", rec_turnover);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_asset_turnover() {
-    println!("Enter revenue: ");
-    let revenue = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter total assets: ");
-    let total_assets = get_user_input().trim().parse::<f64>().unwrap();
-
-    let asset_turnover = a_t(revenue, total_assets);
-    println!("Asset Turnover: {:.2}", asset_turnover);
-    {
-        let result = format!("Asset Turnover = {:.2}", asset_turnover);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
 fn valuation_ratios_menu() {
     clear_terminal();
     display_ascii_art();
@@ -478,65 +313,33 @@ fn valuation_ratios_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_price_to_earnings_ratio(),
-        "2" => run_price_to_book_ratio(),
-        "3" => run_dividend_yield(),
+        "1" => run_calculator(&PRICE_TO_EARNINGS_RATIO, valuation_ratios_menu),
+        "2" => run_calculator(&PRICE_TO_BOOK_RATIO, valuation_ratios_menu),
+        "3" => run_calculator(&DIVIDEND_YIELD, valuation_ratios_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_price_to_earnings_ratio() {
-    println!("Enter share price: ");
-    let share_price = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter earnings per share: ");
-    let earnings_per_share = get_user_input().trim().parse::<f64>().unwrap();
-
-    let pte = p_t_e(share_price, earnings_per_share);
-    println!("Price to Earnings (P/E) Ratio: {:.2}", pte);
-    {
-        let result = format!("Price to Earnings Ratio = {:.2}", pte);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_price_to_book_ratio() {
-    println!("Enter share price: ");
-    let share_price = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter book value per share: ");
-    let book_value_per_share = get_user_input().trim().parse::<f64>().unwrap();
-
-    let ptb = p_t_b(share_price, book_value_per_share);
-    println!("Price to Book (P/B) Ratio: {:.2}", ptb);
-    {
-        let result = format!("Price to Book Ratio = {:.2}", ptb);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_dividend_yield() {
-    println!("Enter annual dividends per share: ");
-    let annual_dividends_per_share = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter share price: ");
-    let share_price = get_user_input().trim().parse::<f64>().unwrap();
-
-    let div_yield = div_y(annual_dividends_per_share, share_price);
-    println!("Dividend Yield: {:.2}", div_yield);
-    {
-        let result = format!("Dividend Yield = {:.2}", div_yield);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
 fn options_menu() {
     clear_terminal();
     display_ascii_art();
     println!("\nOptions Menu:");
     println!("1. Black-Scholes-Merton");
+    println!("2. Implied Volatility (Black-Scholes-Merton)");
+    println!("3. American Option - Binomial Tree");
+    println!("4. American Option - Trinomial Tree");
+    println!("5. Asian Option - Monte Carlo");
+    println!("6. Lookback Option - Monte Carlo");
+    println!("7. Barrier Option - Monte Carlo");
+    println!("8. Black-76 (Futures and Forwards)");
+    println!("9. Implied Volatility (Black-76)");
+    println!("10. Bachelier (Normal Model)");
+    println!("11. Implied Normal Volatility (Bachelier)");
+    println!("12. Option Strategy Builder");
+    println!("13. Put-Call Parity Check");
+    println!("14. Volatility Surface from Option Chain");
     println!("--------------");
     println!("h. History");
     println!("b. Back to Main Menu");
@@ -545,44 +348,47 @@ fn options_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_bsm(),
+        "1" => run_calculator(&BSM, options_menu),
+        "2" => run_calculator(&BSM_IMPLIED_VOL, options_menu),
+        "3" => run_calculator(&BINOMIAL_TREE, options_menu),
+        "4" => run_calculator(&TRINOMIAL_TREE, options_menu),
+        "5" => run_calculator(&MONTE_CARLO_ASIAN, options_menu),
+        "6" => run_calculator(&MONTE_CARLO_LOOKBACK, options_menu),
+        "7" => run_calculator(&MONTE_CARLO_BARRIER, options_menu),
+        "8" => run_calculator(&BLACK76, options_menu),
+        "9" => run_calculator(&BLACK76_IMPLIED_VOL, options_menu),
+        "10" => run_calculator(&BACHELIER, options_menu),
+        "11" => run_calculator(&BACHELIER_IMPLIED_VOL, options_menu),
+        "12" => run_calculator(&STRATEGY, options_menu),
+        "13" => run_calculator(&PUT_CALL_PARITY, options_menu),
+        "14" => run_calculator(&VOL_SURFACE, options_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_bsm() {
-    println!("Enter stock price: ");
-    let stock_price = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter strike price: ");
-    let strike_price = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter time to expiration (years): ");
-    let time_to_expiration = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter risk-free rate: ");
-    let risk_free_rate = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter volatility: ");
-    let volatility = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter dividend yield: ");
-    let dividend_yield = get_user_input().trim().parse::<f64>().unwrap();
-
-    let (call_price, put_price, nd1, nd2) = bsm(
-        stock_price,
-        strike_price,
-        time_to_expiration,
-        risk_free_rate,
-        volatility,
-        dividend_yield,
-    );
+fn bonds_menu() {
+    clear_terminal();
+    display_ascii_art();
+    println!("\nBonds Menu:");
+    println!("1. Bond Price from Yield");
+    println!("2. Yield to Maturity and Call");
+    println!("3. Duration, Convexity and DV01");
+    println!("--------------");
+    println!("h. History");
+    println!("b. Back to Main Menu");
+    print!("Enter your choice: ");
+    io::stdout().flush().unwrap();
 
-    println!("Call Price: {:.2}", call_price);
-    println!("Put Price: {:.2}", put_price);
-    println!("N(d1): {:.2}", nd1);
-    println!("N(d2): {:.2}", nd2);
-    {
-        let result = format!("BSM Call Price = {:.2}, Put Price = {:.2}", call_price, put_price);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
+    let choice = get_user_input();
+    match choice.trim() {
+        "1" => run_calculator(&BOND_PRICE, bonds_menu),
+        "2" => run_calculator(&BOND_YIELD, bonds_menu),
+        "3" => run_calculator(&BOND_DURATION, bonds_menu),
+        "H" | "h" => display_history(),
+        "b" => {}
+        _ => println!("Invalid option, please try again."),
     }
 }
 
@@ -592,6 +398,15 @@ fn time_value_of_money_menu() {
     println!("\nTime Value of Money Menu:");
     println!("1. Calculate XNPV");
     println!("2. Calculate XIRR");
+    println!("3. Periodic Cash Flow Analysis (NPV, IRR, MIRR, Payback)");
+    println!("4. Solve for Present Value (PV)");
+    println!("5. Solve for Future Value (FV)");
+    println!("6. Solve for Payment (PMT)");
+    println!("7. Solve for Number of Periods (N)");
+    println!("8. Solve for Interest Rate");
+    println!("9. Growing Perpetuity");
+    println!("10. Loan Amortization Schedule");
+    println!("11. Cash Flow Duration, Convexity and DV01");
     println!("--------------");
     println!("h. History");
     println!("b. Back to Main Menu");
@@ -600,64 +415,23 @@ fn time_value_of_money_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_xnpv(),
-        "2" => run_xirr(),
+        "1" => run_calculator(&XNPV, time_value_of_money_menu),
+        "2" => run_calculator(&XIRR, time_value_of_money_menu),
+        "3" => run_calculator(&PERIODIC_CASHFLOWS, time_value_of_money_menu),
+        "4" => run_calculator(&TVM_PRESENT_VALUE, time_value_of_money_menu),
+        "5" => run_calculator(&TVM_FUTURE_VALUE, time_value_of_money_menu),
+        "6" => run_calculator(&TVM_PAYMENT, time_value_of_money_menu),
+        "7" => run_calculator(&TVM_PERIODS, time_value_of_money_menu),
+        "8" => run_calculator(&TVM_RATE_SOLVER, time_value_of_money_menu),
+        "9" => run_calculator(&PERPETUITY, time_value_of_money_menu),
+        "10" => run_calculator(&LOAN_AMORTIZATION, time_value_of_money_menu),
+        "11" => run_calculator(&CASHFLOW_DURATION, time_value_of_money_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_xnpv() {
-    let cashflows = get_cashflows();
-    println!("Enter discount rate (as a decimal, e.g., 0.05 for 5%): ");
-    let discount_rate = get_user_input().trim().parse::<f64>().unwrap();
-
-    // Convert Vec<(f64, String)> to Vec<(f64, &str)>
-    let cashflows_ref: Vec<(f64, &str)> = cashflows.iter().map(|(amt, date)| (*amt, date.as_str())).collect();
-
-    let npv = xnpv(cashflows_ref, discount_rate);
-    println!("XNPV: {:.2}", npv);
-    {
-        let result = format!("XNPV = {:.2}", npv);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_xirr() {
-    let cashflows = get_cashflows();
-
-    // Convert Vec<(f64, String)> to Vec<(f64, &str)>
-    let cashflows_ref: Vec<(f64, &str)> = cashflows.iter().map(|(amt, date)| (*amt, date.as_str())).collect();
-
-    let irr = xirr(cashflows_ref);
-    println!("XIRR: {:.6}", irr);
-    {
-        let result = format!("XIRR = {:.6}", irr);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn get_cashflows() -> Vec<(f64, String)> {
-    let mut cashflows = Vec::new();
-    loop {
-        println!("Enter cash flow amount (or 'done' to finish): ");
-        let amount_input = get_user_input().trim().to_string();
-        if amount_input.to_lowercase() == "done" {
-            break;
-        }
-        let amount = amount_input.parse::<f64>().expect("Invalid amount");
-
-        println!("Enter date for this cash flow (YYYY-MM-DD): ");
-        let date = get_user_input().trim().to_string();
-
-        cashflows.push((amount, date));
-    }
-    cashflows
-}
-
 fn build_ups_menu() {
     clear_terminal();
     display_ascii_art();
@@ -674,100 +448,16 @@ fn build_ups_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_fcff_ni(),
-        "2" => run_fcff_cfo(),
-        "3" => run_fcff_ebit(),
-        "4" => run_fcff_ebitda(),
+        "1" => run_calculator(&FCFF_NI, build_ups_menu),
+        "2" => run_calculator(&FCFF_CFO, build_ups_menu),
+        "3" => run_calculator(&FCFF_EBIT, build_ups_menu),
+        "4" => run_calculator(&FCFF_EBITDA, build_ups_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_fcff_ni() {
-    println!("Enter net income: ");
-    let net_income = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter non-cash charges: ");
-    let non_cash_charges = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter interest: ");
-    let interest = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter capital expenditures (CapEx): ");
-    let capex = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter change in working capital: ");
-    let change_in_working_capital = get_user_input().trim().parse::<f64>().unwrap();
-
-    let fcff = fcff_ni(net_income, non_cash_charges, interest, tax_rate, capex, change_in_working_capital);
-    println!("FCFF using Net Income: {:.2}", fcff);
-    {
-        let result = format!("FCFF using Net Income = {:.2}", fcff);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_fcff_cfo() {
-    println!("Enter cash flow from operations (CFO): ");
-    let cfo = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter interest expense: ");
-    let interest_expense = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter capital expenditures (CapEx): ");
-    let capex = get_user_input().trim().parse::<f64>().unwrap();
-
-    let fcff = fcff_cfo(cfo, interest_expense, tax_rate, capex);
-    println!("FCFF using CFO: {:.2}", fcff);
-    {
-        let result = format!("FCFF using CFO = {:.2}", fcff);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_fcff_ebit() {
-    println!("Enter EBIT: ");
-    let ebit = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter depreciation: ");
-    let depreciation = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter capital expenditures (CapEx): ");
-    let capex = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter change in working capital: ");
-    let change_in_working_capital = get_user_input().trim().parse::<f64>().unwrap();
-
-    let fcff = fcff_ebit(ebit, tax_rate, depreciation, capex, change_in_working_capital);
-    println!("FCFF using EBIT: {:.2}", fcff);
-    {
-        let result = format!("FCFF using EBIT = {:.2}", fcff);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_fcff_ebitda() {
-    println!("Enter EBITDA: ");
-    let ebitda = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter depreciation: ");
-    let depreciation = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter capital expenditures (CapEx): ");
-    let capex = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter change in working capital: ");
-    let change_in_working_capital = get_user_input().trim().parse::<f64>().unwrap();
-
-    let fcff = fcff_ebitda(ebitda, tax_rate, depreciation, capex, change_in_working_capital);
-    println!("FCFF using EBITDA: {:.2}", fcff);
-    {
-        let result = format!("FCFF using EBITDA = {:.2}", fcff);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
 fn wacc_menu() {
     clear_terminal();
     display_ascii_art();
@@ -786,142 +476,18 @@ fn wacc_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_wacc_coe(),
-        "2" => run_coe(),
-        "3" => run_wacc_beta(),
-        "4" => run_mrp(),
-        "5" => run_equity_beta(),
-        "6" => run_asset_beta(),
+        "1" => run_calculator(&WACC_COE, wacc_menu),
+        "2" => run_calculator(&COE, wacc_menu),
+        "3" => run_calculator(&WACC_BETA, wacc_menu),
+        "4" => run_calculator(&MRP, wacc_menu),
+        "5" => run_calculator(&EQUITY_BETA, wacc_menu),
+        "6" => run_calculator(&ASSET_BETA, wacc_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_wacc_coe() {
-    println!("Enter cost of equity (COE): ");
-    let coe = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter weight of equity (WE): ");
-    let we = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter cost of debt (COD): ");
-    let cod = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter weight of debt (WD): ");
-    let wd = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter cost of preferred stock (COP): ");
-    let cop = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter weight of preferred stock (WP): ");
-    let wp = get_user_input().trim().parse::<f64>().unwrap();
-
-    let wacc = wacc_coe(coe, we, tax_rate, cod, wd, cop, wp);
-    println!("WACC using Cost of Equity: {:.2}", wacc);
-    {
-        let result = format!("WACC using Cost of Equity = {:.2}", wacc);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_coe() {
-    println!("Enter risk-free rate (RFR): ");
-    let rfr = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter equity beta: ");
-    let equity_beta = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter market risk premium (MRP): ");
-    let mrp = get_user_input().trim().parse::<f64>().unwrap();
-
-    let coe_value = coe(rfr, equity_beta, mrp);
-    println!("Cost of Equity (COE): {:.2}", coe_value);
-    {
-        let result = format!("Cost of Equity = {:.2}", coe_value);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_wacc_beta() {
-    println!("Enter equity beta: ");
-    let equity_beta = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter risk-free rate (RFR): ");
-    let rfr = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter market risk premium (MRP): ");
-    let mrp = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter weight of equity (WE): ");
-    let we = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter cost of debt (COD): ");
-    let cod = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter weight of debt (WD): ");
-    let wd = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter cost of preferred stock (COP): ");
-    let cop = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter weight of preferred stock (WP): ");
-    let wp = get_user_input().trim().parse::<f64>().unwrap();
-
-    let wacc = wacc_beta(equity_beta, rfr, mrp, we, tax_rate, cod, wd, cop, wp);
-    println!("WACC using Equity Beta: {:.2}", wacc);
-    {
-        let result = format!("WACC using Equity Beta = {:.2}", wacc);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_mrp() {
-    println!("Enter equity market return: ");
-    let equity_market_return = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter risk-free rate (RFR): ");
-    let rfr = get_user_input().trim().parse::<f64>().unwrap();
-
-    let mrp_value = mrp(equity_market_return, rfr);
-    println!("Market Risk Premium (MRP): {:.2}", mrp_value);
-    {
-        let result = format!("Market Risk Premium = {:.2}", mrp_value);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_equity_beta() {
-    println!("Enter equity: ");
-    let equity = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter debt: ");
-    let debt = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter asset beta: ");
-    let asset_beta_value = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-
-    let equity_beta_value = equity_beta(equity, debt, asset_beta_value, tax_rate);
-    println!("Equity Beta: {:.2}", equity_beta_value);
-    {
-        let result = format!("Equity Beta = {:.2}", equity_beta_value);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
-fn run_asset_beta() {
-    println!("Enter equity: ");
-    let equity = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter debt: ");
-    let debt = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter equity beta: ");
-    let equity_beta_value = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter tax rate: ");
-    let tax_rate = get_user_input().trim().parse::<f64>().unwrap();
-
-    let asset_beta_value = asset_beta(equity, debt, equity_beta_value, tax_rate);
-    println!("Asset Beta: {:.2}", asset_beta_value);
-    {
-        let result = format!("Asset Beta = {:.2}", asset_beta_value);
-        *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-        HISTORY.lock().unwrap().push(result);
-    }
-}
-
 fn valuation_menu() {
     clear_terminal();
     display_ascii_art();
@@ -936,55 +502,13 @@ fn valuation_menu() {
 
     let choice = get_user_input();
     match choice.trim() {
-        "1" => run_ggm_p1(),
-        "2" => run_ggm_p2(),
+        "1" => run_calculator(&GGM_P1, valuation_menu),
+        "2" => run_calculator(&GGM_P2, valuation_menu),
         "H" | "h" => display_history(),
-        "b" => return,
+        "b" => {}
         _ => println!("Invalid option, please try again."),
     }
 }
 
-fn run_ggm_p1() {
-    println!("Enter initial cash flow (Cashflow_0): ");
-    let cashflow_0 = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter required rate of return (as a decimal, e.g., 0.05 for 5%): ");
-    let required_rate_of_return = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter growth rate (as a decimal, e.g., 0.02 for 2%): ");
-    let growth_rate = get_user_input().trim().parse::<f64>().unwrap();
-
-    match ggm_p1(cashflow_0, required_rate_of_return, growth_rate) {
-        Some(value) => {
-            println!("Valuation using GGM One Phase: {:.2}", value);
-            let result = format!("GGM One Phase Valuation = {:.2}", value);
-            *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-            HISTORY.lock().unwrap().push(result);
-        },
-        None => println!("Invalid input: Required rate of return must be greater than growth rate."),
-    }
-}
-
-fn run_ggm_p2() {
-    println!("Enter initial cash flow (Cashflow_0): ");
-    let cashflow_0 = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter required rate of return (as a decimal, e.g., 0.05 for 5%): ");
-    let required_rate_of_return = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter first phase growth rate (as a decimal, e.g., 0.03 for 3%): ");
-    let growth_rate_1 = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter second phase growth rate (as a decimal, e.g., 0.02 for 2%): ");
-    let growth_rate_2 = get_user_input().trim().parse::<f64>().unwrap();
-    println!("Enter number of periods for first phase: ");
-    let periods = get_user_input().trim().parse::<u32>().unwrap();
-
-    match ggm_p2(cashflow_0, required_rate_of_return, growth_rate_1, growth_rate_2, periods) {
-        Some(value) => {
-            println!("Valuation using GGM Two Phase: {:.2}", value);
-            let result = format!("GGM Two Phase Valuation = {:.2}", value);
-            *LAST_RESULT.lock().unwrap() = format!("Last: {}", result);
-            HISTORY.lock().unwrap().push(result);
-        },
-        None => println!("Invalid input: Required rate of return must be greater than second phase growth rate."),
-    }
-}
-
 // end of file
 
@@ -0,0 +1,644 @@
+use ifch::calc_nd;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, StandardNormal};
+
+// Option pricing helpers that build on the ifch `bsm` model
+
+pub fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+// Sensitivities of one option; vega and the rhos are per 1.00 change, theta per year
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    pub vega: f64,
+    pub theta: f64,
+    pub rho: f64,
+    pub dividend_rho: f64,
+}
+
+fn bsm_d1_d2(s: f64, k: f64, t: f64, r: f64, sigma: f64, q: f64) -> (f64, f64) {
+    let d1 = ((s / k).ln() + (r - q + 0.5 * sigma * sigma) * t) / (sigma * t.sqrt());
+    (d1, d1 - sigma * t.sqrt())
+}
+
+// Call and put Greeks under Black-Scholes-Merton with continuous dividend yield `q`
+pub fn bsm_greeks(s: f64, k: f64, t: f64, r: f64, sigma: f64, q: f64) -> (Greeks, Greeks) {
+    let (d1, d2) = bsm_d1_d2(s, k, t, r, sigma, q);
+    let dividend_discount = (-q * t).exp();
+    let discount = (-r * t).exp();
+    let density = norm_pdf(d1);
+
+    let gamma = dividend_discount * density / (s * sigma * t.sqrt());
+    let vega = s * dividend_discount * density * t.sqrt();
+    let time_decay = -s * dividend_discount * density * sigma / (2.0 * t.sqrt());
+
+    let call = Greeks {
+        delta: dividend_discount * calc_nd(d1),
+        gamma,
+        vega,
+        theta: time_decay - r * k * discount * calc_nd(d2) + q * s * dividend_discount * calc_nd(d1),
+        rho: k * t * discount * calc_nd(d2),
+        dividend_rho: -s * t * dividend_discount * calc_nd(d1),
+    };
+    let put = Greeks {
+        delta: -dividend_discount * calc_nd(-d1),
+        gamma,
+        vega,
+        theta: time_decay + r * k * discount * calc_nd(-d2) - q * s * dividend_discount * calc_nd(-d1),
+        rho: -k * t * discount * calc_nd(-d2),
+        dividend_rho: s * t * dividend_discount * calc_nd(-d1),
+    };
+    (call, put)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+impl OptionKind {
+    // From a "call"/"put" choice input
+    pub fn from_choice(choice: &str) -> OptionKind {
+        if choice == "put" { OptionKind::Put } else { OptionKind::Call }
+    }
+}
+
+pub fn bsm_price(kind: OptionKind, s: f64, k: f64, t: f64, r: f64, sigma: f64, q: f64) -> f64 {
+    let (call, put, _, _) = ifch::bsm(s, k, t, r, sigma, q);
+    match kind {
+        OptionKind::Call => call,
+        OptionKind::Put => put,
+    }
+}
+
+// No-arbitrage price range of a European option: discounted intrinsic value up to the
+// discounted stock (call) or strike (put)
+pub fn bsm_bounds(kind: OptionKind, s: f64, k: f64, t: f64, r: f64, q: f64) -> (f64, f64) {
+    let forward_stock = s * (-q * t).exp();
+    let discounted_strike = k * (-r * t).exp();
+    match kind {
+        OptionKind::Call => ((forward_stock - discounted_strike).max(0.0), forward_stock),
+        OptionKind::Put => ((discounted_strike - forward_stock).max(0.0), discounted_strike),
+    }
+}
+
+pub struct ImpliedVol {
+    pub volatility: f64,
+    pub iterations: u32,
+    pub residual: f64, // model price minus market price at the solution
+}
+
+const MAX_ITERATIONS: u32 = 200;
+
+// Volatility at which `price` reproduces `target`, for a price that increases with volatility
+// from `bounds.0` (zero vol) towards `bounds.1`. Newton steps are kept inside a bisection bracket,
+// so the solver cannot diverge; `initial_high` sets the scale of the first bracket.
+pub fn implied_vol(
+    target: f64,
+    bounds: (f64, f64),
+    initial_high: f64,
+    price: impl Fn(f64) -> f64,
+    vega: impl Fn(f64) -> f64,
+) -> Result<ImpliedVol, String> {
+    let (lower, upper) = bounds;
+    let tolerance = 1e-10 * target.abs().max(1.0);
+    if target <= lower + tolerance {
+        return Err(format!(
+            "no implied volatility exists: price {:.6} is at or below the no-arbitrage lower bound {:.6}",
+            target, lower
+        ));
+    }
+    if target >= upper {
+        return Err(format!(
+            "no implied volatility exists: price {:.6} is at or above the no-arbitrage upper bound {:.6}",
+            target, upper
+        ));
+    }
+
+    let mut low = 0.0;
+    let mut high = initial_high;
+    let mut expansions = 0;
+    while price(high) < target {
+        low = high;
+        high *= 2.0;
+        expansions += 1;
+        if expansions > 20 {
+            return Err(format!("no implied volatility exists: no volatility up to {} reproduces price {:.6}", high, target));
+        }
+    }
+
+    let mut sigma = 0.5 * (low + high);
+    for iteration in 1..=MAX_ITERATIONS {
+        let difference = price(sigma) - target;
+        if difference.abs() < tolerance || high - low < 1e-14 * high {
+            return Ok(ImpliedVol { volatility: sigma, iterations: iteration, residual: difference });
+        }
+        if difference < 0.0 {
+            low = sigma;
+        } else {
+            high = sigma;
+        }
+        let slope = vega(sigma);
+        let newton = sigma - difference / slope;
+        sigma = if slope > 0.0 && newton > low && newton < high { newton } else { 0.5 * (low + high) };
+    }
+    Err(format!("implied volatility did not converge in {} iterations", MAX_ITERATIONS))
+}
+
+pub fn bsm_implied_vol(kind: OptionKind, target: f64, s: f64, k: f64, t: f64, r: f64, q: f64) -> Result<ImpliedVol, String> {
+    implied_vol(
+        target,
+        bsm_bounds(kind, s, k, t, r, q),
+        1.0,
+        |sigma| bsm_price(kind, s, k, t, r, sigma, q),
+        |sigma| bsm_greeks(s, k, t, r, sigma, q).0.vega,
+    )
+}
+
+// A plain call or put, shared by the tree and simulation pricers
+pub struct Vanilla {
+    pub kind: OptionKind,
+    pub spot: f64,
+    pub strike: f64,
+    pub time: f64,
+    pub rate: f64,
+    pub volatility: f64,
+    pub dividend_yield: f64,
+}
+
+impl Vanilla {
+    pub fn payoff(&self, spot: f64) -> f64 {
+        match self.kind {
+            OptionKind::Call => (spot - self.strike).max(0.0),
+            OptionKind::Put => (self.strike - spot).max(0.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Tree {
+    Binomial,  // Cox-Ross-Rubinstein
+    Trinomial, // Boyle, with a middle branch that keeps the price unchanged
+}
+
+pub const MAX_TREE_STEPS: u32 = 5000;
+
+// Present value at time `at` of the discrete dividends paid after it
+pub fn dividends_after(dividends: &[(f64, f64)], rate: f64, at: f64) -> f64 {
+    dividends
+        .iter()
+        .filter(|(time, _)| *time > at)
+        .map(|(time, amount)| amount * (-rate * (time - at)).exp())
+        .sum()
+}
+
+pub fn validate_dividends(dividends: &[(f64, f64)], time: f64) -> Result<(), String> {
+    for (at, amount) in dividends {
+        if *at <= 0.0 || *at > time {
+            return Err(format!("dividend time {} must be after today and no later than expiration ({})", at, time));
+        }
+        if *amount < 0.0 {
+            return Err(format!("dividend amount {} must not be negative", amount));
+        }
+    }
+    Ok(())
+}
+
+// Price of `option` on a lattice with `steps` time steps. Discrete dividends `(time, amount)`
+// follow the escrowed dividend model: the tree models the stock less the present value of the
+// dividends still to come, which is added back when checking early exercise.
+pub fn tree_price(option: &Vanilla, dividends: &[(f64, f64)], tree: Tree, steps: u32, american: bool) -> Result<f64, String> {
+    if steps == 0 || steps > MAX_TREE_STEPS {
+        return Err(format!("steps must be between 1 and {}", MAX_TREE_STEPS));
+    }
+    validate_dividends(dividends, option.time)?;
+    let escrowed_spot = option.spot - dividends_after(dividends, option.rate, 0.0);
+    if escrowed_spot <= 0.0 {
+        return Err("present value of the dividends must be less than the stock price".to_string());
+    }
+
+    let n = steps as usize;
+    let dt = option.time / steps as f64;
+    let discount = (-option.rate * dt).exp();
+    let drift = option.rate - option.dividend_yield;
+    let sigma = option.volatility;
+
+    // Branch factor, probabilities (down, middle, up) and number of nodes gained per step
+    let (up, probabilities, width) = match tree {
+        Tree::Binomial => {
+            let up = (sigma * dt.sqrt()).exp();
+            let p = ((drift * dt).exp() - 1.0 / up) / (up - 1.0 / up);
+            (up, [1.0 - p, 0.0, p], 1)
+        }
+        Tree::Trinomial => {
+            let up = (sigma * (3.0 * dt).sqrt()).exp();
+            let skew = (dt / (12.0 * sigma * sigma)).sqrt() * (drift - 0.5 * sigma * sigma);
+            (up, [1.0 / 6.0 - skew, 2.0 / 3.0, 1.0 / 6.0 + skew], 2)
+        }
+    };
+    if probabilities.iter().any(|p| !(0.0..=1.0).contains(p)) {
+        return Err(format!("{} steps are too few for this volatility and rate; use more steps", steps));
+    }
+
+    // Node i at step j sits `i - j` (binomial: `2i - j`) up-moves from the start
+    let level = |step: usize, node: usize| match tree {
+        Tree::Binomial => 2 * node as i64 - step as i64,
+        Tree::Trinomial => node as i64 - step as i64,
+    };
+    let stock = |step: usize, node: usize| {
+        escrowed_spot * up.powi(level(step, node) as i32) + dividends_after(dividends, option.rate, step as f64 * dt)
+    };
+
+    let mut values: Vec<f64> = (0..=width * n).map(|node| option.payoff(stock(n, node))).collect();
+    for step in (0..n).rev() {
+        values = (0..=width * step)
+            .map(|node| {
+                let continuation = match tree {
+                    Tree::Binomial => probabilities[0] * values[node] + probabilities[2] * values[node + 1],
+                    Tree::Trinomial => {
+                        probabilities[0] * values[node] + probabilities[1] * values[node + 1] + probabilities[2] * values[node + 2]
+                    }
+                };
+                let held = discount * continuation;
+                if american { held.max(option.payoff(stock(step, node))) } else { held }
+            })
+            .collect();
+    }
+    Ok(values[0])
+}
+
+// Path-dependent payoffs priced by simulation
+#[derive(Clone, Copy)]
+pub enum PathPayoff {
+    Asian { geometric: bool },
+    Lookback { floating: bool },
+    Barrier { up: bool, knock_in: bool, level: f64 },
+}
+
+impl PathPayoff {
+    // Payoff on a path of prices at each monitoring date, starting with today's spot
+    fn value(self, option: &Vanilla, path: &[f64]) -> f64 {
+        let last = path[path.len() - 1];
+        match self {
+            PathPayoff::Asian { geometric } => {
+                let fixings = &path[1..];
+                let average = if geometric {
+                    (fixings.iter().map(|s| s.ln()).sum::<f64>() / fixings.len() as f64).exp()
+                } else {
+                    fixings.iter().sum::<f64>() / fixings.len() as f64
+                };
+                option.payoff(average)
+            }
+            PathPayoff::Lookback { floating } => {
+                let high = path.iter().copied().fold(f64::MIN, f64::max);
+                let low = path.iter().copied().fold(f64::MAX, f64::min);
+                match (floating, option.kind) {
+                    (false, OptionKind::Call) => (high - option.strike).max(0.0),
+                    (false, OptionKind::Put) => (option.strike - low).max(0.0),
+                    (true, OptionKind::Call) => last - low,
+                    (true, OptionKind::Put) => high - last,
+                }
+            }
+            PathPayoff::Barrier { up, knock_in, level } => {
+                let touched = path.iter().any(|s| if up { *s >= level } else { *s <= level });
+                if touched == knock_in { option.payoff(last) } else { 0.0 }
+            }
+        }
+    }
+}
+
+pub struct Simulation {
+    pub paths: u32,
+    pub steps: u32, // monitoring dates, evenly spaced up to expiration
+    pub seed: u64,
+    pub antithetic: bool,
+    pub control_variate: bool,
+}
+
+pub struct MonteCarlo {
+    pub price: f64,
+    pub standard_error: f64,
+    pub vanilla_price: f64, // plain simulated price of the European option, for comparison with BSM
+    pub bsm_price: f64,
+}
+
+pub const MAX_SIMULATED_STEPS: u64 = 200_000_000;
+
+// Running means and co-moments of the discounted path payoffs (y) and European payoffs (x),
+// updated one path at a time (Welford) so no path has to be kept
+#[derive(Default)]
+struct Moments {
+    n: f64,
+    mean_x: f64,
+    mean_y: f64,
+    sxx: f64,
+    syy: f64,
+    sxy: f64,
+}
+
+impl Moments {
+    fn add(&mut self, y: f64, x: f64) {
+        self.n += 1.0;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / self.n;
+        self.mean_y += dy / self.n;
+        self.sxx += dx * (x - self.mean_x);
+        self.syy += dy * (y - self.mean_y);
+        self.sxy += dx * (y - self.mean_y);
+    }
+
+    // Mean and standard error of y - beta * (x - x_exact)
+    fn estimate(&self, beta: f64, x_exact: f64) -> (f64, f64) {
+        let variance = (self.syy - 2.0 * beta * self.sxy + beta * beta * self.sxx).max(0.0) / (self.n - 1.0);
+        (self.mean_y - beta * (self.mean_x - x_exact), (variance / self.n).sqrt())
+    }
+}
+
+// Simulates the geometric Brownian motion `bsm` assumes, from a seeded generator so runs repeat.
+// With antithetic paths each sample averages a path and its mirror; the control variate is the
+// European payoff on the same paths, whose exact value is the `bsm` price.
+pub fn monte_carlo(option: &Vanilla, payoff: PathPayoff, simulation: &Simulation) -> Result<MonteCarlo, String> {
+    if simulation.paths < 2 {
+        return Err("at least 2 paths are needed for a standard error".to_string());
+    }
+    if simulation.steps == 0 {
+        return Err("at least 1 step is needed".to_string());
+    }
+    let draws = simulation.paths as u64 * simulation.steps as u64 * if simulation.antithetic { 2 } else { 1 };
+    if draws > MAX_SIMULATED_STEPS {
+        return Err(format!("paths x steps (x2 with antithetic variates) must not exceed {}", MAX_SIMULATED_STEPS));
+    }
+
+    let mut rng = StdRng::seed_from_u64(simulation.seed);
+    let dt = option.time / simulation.steps as f64;
+    let drift = (option.rate - option.dividend_yield - 0.5 * option.volatility.powi(2)) * dt;
+    let diffusion = option.volatility * dt.sqrt();
+    let discount = (-option.rate * option.time).exp();
+
+    let mut shocks = vec![0.0; simulation.steps as usize];
+    let mut path = vec![option.spot; simulation.steps as usize + 1];
+    let mut simulate = |shocks: &[f64], sign: f64| {
+        for (step, shock) in shocks.iter().enumerate() {
+            path[step + 1] = path[step] * (drift + sign * diffusion * shock).exp();
+        }
+        (payoff.value(option, &path), option.payoff(path[path.len() - 1]))
+    };
+
+    let mut moments = Moments::default();
+    for _ in 0..simulation.paths {
+        for shock in shocks.iter_mut() {
+            *shock = StandardNormal.sample(&mut rng);
+        }
+        let (mut sample, mut control) = simulate(&shocks, 1.0);
+        if simulation.antithetic {
+            let (mirror_sample, mirror_control) = simulate(&shocks, -1.0);
+            sample = 0.5 * (sample + mirror_sample);
+            control = 0.5 * (control + mirror_control);
+        }
+        moments.add(discount * sample, discount * control);
+    }
+
+    let bsm_price = bsm_price(option.kind, option.spot, option.strike, option.time, option.rate, option.volatility, option.dividend_yield);
+    // beta = cov(sample, control) / var(control), estimated from the same paths
+    let beta = if simulation.control_variate && moments.sxx > 0.0 { moments.sxy / moments.sxx } else { 0.0 };
+    let (price, standard_error) = moments.estimate(beta, bsm_price);
+
+    Ok(MonteCarlo {
+        price,
+        standard_error,
+        vanilla_price: moments.mean_x,
+        bsm_price,
+    })
+}
+
+// Black-76: European options on a forward or future `f`, discounted at `r`
+pub fn black76_price(kind: OptionKind, f: f64, k: f64, t: f64, r: f64, sigma: f64) -> f64 {
+    let d1 = ((f / k).ln() + 0.5 * sigma * sigma * t) / (sigma * t.sqrt());
+    let d2 = d1 - sigma * t.sqrt();
+    let discount = (-r * t).exp();
+    match kind {
+        OptionKind::Call => discount * (f * calc_nd(d1) - k * calc_nd(d2)),
+        OptionKind::Put => discount * (k * calc_nd(-d2) - f * calc_nd(-d1)),
+    }
+}
+
+// Black-76 call and put Greeks; delta and gamma are to the forward, rho holds the forward fixed
+// and there is no dividend rho
+pub fn black76_greeks(f: f64, k: f64, t: f64, r: f64, sigma: f64) -> (Greeks, Greeks) {
+    let d1 = ((f / k).ln() + 0.5 * sigma * sigma * t) / (sigma * t.sqrt());
+    let discount = (-r * t).exp();
+    let density = norm_pdf(d1);
+    let gamma = discount * density / (f * sigma * t.sqrt());
+    let vega = discount * f * density * t.sqrt();
+    let time_decay = -discount * f * density * sigma / (2.0 * t.sqrt());
+    let greeks = |kind: OptionKind, delta: f64| {
+        let price = black76_price(kind, f, k, t, r, sigma);
+        Greeks { delta, gamma, vega, theta: time_decay + r * price, rho: -t * price, dividend_rho: 0.0 }
+    };
+    (greeks(OptionKind::Call, discount * calc_nd(d1)), greeks(OptionKind::Put, -discount * calc_nd(-d1)))
+}
+
+pub fn black76_implied_vol(kind: OptionKind, target: f64, f: f64, k: f64, t: f64, r: f64) -> Result<ImpliedVol, String> {
+    let discount = (-r * t).exp();
+    let bounds = match kind {
+        OptionKind::Call => (discount * (f - k).max(0.0), discount * f),
+        OptionKind::Put => (discount * (k - f).max(0.0), discount * k),
+    };
+    implied_vol(
+        target,
+        bounds,
+        1.0,
+        |sigma| black76_price(kind, f, k, t, r, sigma),
+        |sigma| black76_greeks(f, k, t, r, sigma).0.vega,
+    )
+}
+
+// Bachelier (normal) model: the forward moves by absolute amounts with normal volatility `sigma`,
+// so forwards and strikes may be zero or negative
+pub fn bachelier_price(kind: OptionKind, f: f64, k: f64, t: f64, r: f64, sigma: f64) -> f64 {
+    let spread = sigma * t.sqrt();
+    let d = (f - k) / spread;
+    let discount = (-r * t).exp();
+    match kind {
+        OptionKind::Call => discount * ((f - k) * calc_nd(d) + spread * norm_pdf(d)),
+        OptionKind::Put => discount * ((k - f) * calc_nd(-d) + spread * norm_pdf(d)),
+    }
+}
+
+pub fn bachelier_greeks(f: f64, k: f64, t: f64, r: f64, sigma: f64) -> (Greeks, Greeks) {
+    let d = (f - k) / (sigma * t.sqrt());
+    let discount = (-r * t).exp();
+    let density = norm_pdf(d);
+    let gamma = discount * density / (sigma * t.sqrt());
+    let vega = discount * density * t.sqrt();
+    let time_decay = -discount * density * sigma / (2.0 * t.sqrt());
+    let greeks = |kind: OptionKind, delta: f64| {
+        let price = bachelier_price(kind, f, k, t, r, sigma);
+        Greeks { delta, gamma, vega, theta: time_decay + r * price, rho: -t * price, dividend_rho: 0.0 }
+    };
+    (greeks(OptionKind::Call, discount * calc_nd(d)), greeks(OptionKind::Put, -discount * calc_nd(-d)))
+}
+
+// There is no upper price bound under the normal model; the first bracket is sized from the
+// at-the-money approximation price = discount * sigma * sqrt(t / 2 pi)
+pub fn bachelier_implied_vol(kind: OptionKind, target: f64, f: f64, k: f64, t: f64, r: f64) -> Result<ImpliedVol, String> {
+    let discount = (-r * t).exp();
+    let intrinsic = match kind {
+        OptionKind::Call => (f - k).max(0.0),
+        OptionKind::Put => (k - f).max(0.0),
+    };
+    let at_the_money = target / discount * (2.0 * std::f64::consts::PI / t).sqrt();
+    implied_vol(
+        target,
+        (discount * intrinsic, f64::INFINITY),
+        at_the_money.max(1e-8),
+        |sigma| bachelier_price(kind, f, k, t, r, sigma),
+        |sigma| bachelier_greeks(f, k, t, r, sigma).0.vega,
+    )
+}
+
+// Put-call parity for European options: C - P = S e^(-qT) - K e^(-rT)
+pub struct Parity {
+    pub violation: f64,                      // (C - P) minus its parity value
+    pub implied_rate: Option<f64>,           // rate at which the quotes satisfy parity, given q
+    pub implied_dividend_yield: Option<f64>, // dividend yield at which they do, given r
+}
+
+pub fn put_call_parity(call: f64, put: f64, s: f64, k: f64, t: f64, r: f64, q: f64) -> Parity {
+    let forward_stock = s * (-q * t).exp();
+    let discounted_strike = k * (-r * t).exp();
+    let implied = |value: f64, scale: f64| (value > 0.0).then(|| -(value / scale).ln() / t);
+    Parity {
+        violation: (call - put) - (forward_stock - discounted_strike),
+        implied_rate: implied(forward_stock - call + put, k),
+        implied_dividend_yield: implied(call - put + discounted_strike, s),
+    }
+}
+
+// Violations below this are quote rounding rather than arbitrage
+pub const PARITY_TOLERANCE: f64 = 5e-5;
+
+// The trade that locks in a parity violation today, per option
+pub fn parity_trade(violation: f64, s: f64, k: f64, t: f64, r: f64, q: f64) -> String {
+    let shares = (-q * t).exp();
+    let loan = k * (-r * t).exp();
+    if violation.abs() < PARITY_TOLERANCE {
+        return "\nThe quotes satisfy put-call parity; there is no arbitrage.\n".to_string();
+    }
+    let (option_legs, stock_leg, cash_leg) = if violation > 0.0 {
+        ("Sell 1 call and buy 1 put (the call is rich)", "Buy", format!("Borrow {:.4} until expiry", loan))
+    } else {
+        ("Buy 1 call and sell 1 put (the put is rich)", "Short", format!("Lend {:.4} until expiry", loan))
+    };
+    format!(
+        "\nArbitrage trade:\n  1. {}\n  2. {} {:.4} shares at {:.4} (1 share at expiry with dividends reinvested)\n  3. {}\n  Locks in {:.4} per option today; at expiry the positions offset for any stock price.\n",
+        option_legs,
+        stock_leg,
+        shares,
+        s,
+        cash_leg,
+        violation.abs()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    #[test]
+    fn implied_vol_round_trip() {
+        for kind in [OptionKind::Call, OptionKind::Put] {
+            for sigma in [0.05, 0.25, 1.5] {
+                let price = bsm_price(kind, 100.0, 110.0, 0.75, 0.04, sigma, 0.01);
+                let solution = bsm_implied_vol(kind, price, 100.0, 110.0, 0.75, 0.04, 0.01).unwrap();
+                assert_close(solution.volatility, sigma, 1e-7);
+            }
+        }
+    }
+
+    #[test]
+    fn implied_vol_without_a_solution() {
+        // A call below its discounted intrinsic value or above the stock price has no implied vol
+        let below = bsm_implied_vol(OptionKind::Call, 5.0, 120.0, 100.0, 1.0, 0.05, 0.0).err().unwrap();
+        assert!(below.contains("lower bound"), "{}", below);
+        let above = bsm_implied_vol(OptionKind::Call, 101.0, 100.0, 100.0, 1.0, 0.05, 0.0).err().unwrap();
+        assert!(above.contains("upper bound"), "{}", above);
+        // A price that never reaches the target cannot be bracketed
+        let bracket = implied_vol(1.5, (0.0, 2.0), 1.0, |sigma| sigma / (1.0 + sigma), |sigma| 1.0 / (1.0 + sigma).powi(2)).err().unwrap();
+        assert!(bracket.contains("no volatility up to"), "{}", bracket);
+    }
+
+    fn vanilla(kind: OptionKind) -> Vanilla {
+        Vanilla { kind, spot: 100.0, strike: 105.0, time: 0.5, rate: 0.05, volatility: 0.3, dividend_yield: 0.02 }
+    }
+
+    #[test]
+    fn trees_converge_to_bsm() {
+        for kind in [OptionKind::Call, OptionKind::Put] {
+            let option = vanilla(kind);
+            let exact = bsm_price(kind, 100.0, 105.0, 0.5, 0.05, 0.3, 0.02);
+            for tree in [Tree::Binomial, Tree::Trinomial] {
+                assert_close(tree_price(&option, &[], tree, 2000, false).unwrap(), exact, 0.005);
+            }
+        }
+    }
+
+    #[test]
+    fn american_put_is_worth_at_least_the_european() {
+        let option = Vanilla { dividend_yield: 0.0, ..vanilla(OptionKind::Put) };
+        for tree in [Tree::Binomial, Tree::Trinomial] {
+            let european = tree_price(&option, &[], tree, 500, false).unwrap();
+            let american = tree_price(&option, &[], tree, 500, true).unwrap();
+            assert!(american > european + 0.05, "{} vs {}", american, european);
+        }
+        // Without dividends an American call is never exercised early
+        let call = Vanilla { dividend_yield: 0.0, ..vanilla(OptionKind::Call) };
+        assert_close(tree_price(&call, &[], Tree::Binomial, 500, true).unwrap(), tree_price(&call, &[], Tree::Binomial, 500, false).unwrap(), 1e-9);
+        assert!(tree_price(&call, &[], Tree::Binomial, MAX_TREE_STEPS + 1, true).is_err());
+    }
+
+    fn simulation(steps: u32, antithetic: bool) -> Simulation {
+        Simulation { paths: 10_000, steps, seed: 42, antithetic, control_variate: false }
+    }
+
+    #[test]
+    fn monte_carlo_matches_bsm_and_bounds_path_payoffs() {
+        let option = vanilla(OptionKind::Call);
+        let exact = bsm_price(OptionKind::Call, 100.0, 105.0, 0.5, 0.05, 0.3, 0.02);
+        // With one fixing an Asian option is the European one
+        let european = monte_carlo(&option, PathPayoff::Asian { geometric: false }, &simulation(1, true)).unwrap();
+        assert!((european.price - exact).abs() < 3.0 * european.standard_error, "{} +- {} vs {}", european.price, european.standard_error, exact);
+
+        // Averaging lowers the volatility, and knocking out only ever removes payoffs
+        let asian = monte_carlo(&option, PathPayoff::Asian { geometric: false }, &simulation(50, false)).unwrap();
+        assert!(asian.price + 3.0 * asian.standard_error < exact, "{} vs {}", asian.price, exact);
+        let barrier = PathPayoff::Barrier { up: true, knock_in: false, level: 120.0 };
+        let knocked_out = monte_carlo(&option, barrier, &simulation(50, false)).unwrap();
+        assert!(knocked_out.price <= knocked_out.vanilla_price);
+        assert!(knocked_out.price < exact, "{} vs {}", knocked_out.price, exact);
+
+        // The same seed gives the same price
+        let again = monte_carlo(&option, barrier, &simulation(50, false)).unwrap();
+        assert_eq!(again.price, knocked_out.price);
+        let too_many = Simulation { paths: 2_000_000, ..simulation(100, true) };
+        assert!(monte_carlo(&option, barrier, &too_many).err().unwrap().contains("x2 with antithetic variates"));
+    }
+
+    #[test]
+    fn black76_and_bachelier_parity() {
+        // Call less put is the discounted forward less the discounted strike
+        let discount = (-0.04f64 * 0.75).exp();
+        for sigma in [0.1, 0.3, 0.8] {
+            let parity = black76_price(OptionKind::Call, 95.0, 100.0, 0.75, 0.04, sigma) - black76_price(OptionKind::Put, 95.0, 100.0, 0.75, 0.04, sigma);
+            assert_close(parity, discount * (95.0 - 100.0), 1e-10);
+        }
+        let parity = bachelier_price(OptionKind::Call, 95.0, 100.0, 0.75, 0.04, 20.0) - bachelier_price(OptionKind::Put, 95.0, 100.0, 0.75, 0.04, 20.0);
+        assert_close(parity, discount * (95.0 - 100.0), 1e-10);
+    }
+}
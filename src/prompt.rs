@@ -0,0 +1,327 @@
+use std::path::Path;
+
+use crate::calculators::{Calculator, Field, FieldKind, Inputs, Rule, Value, parse_choice, parse_pairs, validate_cashflows, validate_numbers};
+use crate::cashflows::{self, CashFlow};
+use crate::expr;
+use crate::get_user_input;
+
+// Typed prompts that re-ask on bad input instead of panicking
+
+pub const CANCEL: &str = "cancel";
+
+// Returned when the user types the cancel keyword at a prompt
+pub struct Cancelled;
+
+fn read(prompt: &str) -> Result<String, Cancelled> {
+    println!("{}", prompt);
+    let input = get_user_input().trim().to_string();
+    if input.eq_ignore_ascii_case(CANCEL) {
+        return Err(Cancelled);
+    }
+    Ok(input)
+}
+
+// Parses a number typed at a prompt. Arithmetic, `5%`, `3.5m` and `$1,234.56` are accepted;
+// `ans` and `$name` refer to stored results.
+pub fn parse_number(input: &str) -> Result<f64, String> {
+    expr::evaluate(input).map_err(|message| format!("'{}': {}", input, message))
+}
+
+// Label with the default value shown in brackets; an empty answer keeps the default
+fn label_with_default(label: &str, default: Option<String>) -> String {
+    match default {
+        Some(default) => format!("Enter {} [{}]: ", label, default),
+        None => format!("Enter {}: ", label),
+    }
+}
+
+pub fn prompt_number(label: &str, rule: Rule, default: Option<f64>) -> Result<f64, Cancelled> {
+    loop {
+        let input = read(&label_with_default(label, default.map(|value| value.to_string())))?;
+        if let (true, Some(default)) = (input.is_empty(), default) {
+            return Ok(default);
+        }
+        match parse_number(&input) {
+            Ok(value) => match rule.check(value) {
+                Ok(()) => return Ok(value),
+                Err(message) => println!("Invalid input: {} {}. Please try again.", label, message),
+            },
+            Err(message) => println!("Invalid input: {}. Please try again.", message),
+        }
+    }
+}
+
+pub fn prompt_count(label: &str, default: Option<u32>) -> Result<u32, Cancelled> {
+    loop {
+        let input = read(&label_with_default(label, default.map(|value| value.to_string())))?;
+        if let (true, Some(default)) = (input.is_empty(), default) {
+            return Ok(default);
+        }
+        match input.parse::<u32>() {
+            Ok(value) => return Ok(value),
+            Err(_) => println!("Invalid input: '{}' is not a whole number. Please try again.", input),
+        }
+    }
+}
+
+pub fn prompt_choice(label: &str, options: &'static [&'static str], default: Option<&str>) -> Result<&'static str, Cancelled> {
+    // Options such as day counts contain slashes themselves
+    let separator = if options.iter().any(|option| option.contains('/')) { ", " } else { "/" };
+    let label = format!("{} ({})", label, options.join(separator));
+    loop {
+        let input = read(&label_with_default(&label, default.map(str::to_string)))?;
+        let input = match (input.is_empty(), default) {
+            (true, Some(default)) => default.to_string(),
+            _ => input,
+        };
+        match parse_choice(options, &input) {
+            Ok(option) => return Ok(option),
+            Err(message) => println!("Invalid input: {}. Please try again.", message),
+        }
+    }
+}
+
+pub fn prompt_text(label: &str, validate: fn(&str) -> Result<(), String>, default: Option<&str>) -> Result<String, Cancelled> {
+    loop {
+        let input = read(&label_with_default(label, default.map(str::to_string)))?;
+        let input = match (input.is_empty(), default) {
+            (true, Some(default)) => default.to_string(),
+            _ => input,
+        };
+        match validate(&input) {
+            Ok(()) => return Ok(input),
+            Err(message) => println!("Invalid input: {}. Please try again.", message),
+        }
+    }
+}
+
+// Numbers on one line separated by ';', each of which may be an expression
+pub fn prompt_numbers(label: &str, default: Option<&[f64]>) -> Result<Vec<f64>, Cancelled> {
+    let label = format!("{} separated by ';'", label);
+    let default_text = default.map(|numbers| numbers.iter().map(f64::to_string).collect::<Vec<_>>().join("; "));
+    loop {
+        let input = read(&label_with_default(&label, default_text.clone()))?;
+        if let (true, Some(default)) = (input.is_empty(), default) {
+            return Ok(default.to_vec());
+        }
+        let numbers = input
+            .split(';')
+            .filter(|number| !number.trim().is_empty())
+            .map(|number| parse_number(number.trim()))
+            .collect::<Result<Vec<f64>, String>>()
+            .and_then(|numbers| validate_numbers(&numbers).map(|()| numbers));
+        match numbers {
+            Ok(numbers) => return Ok(numbers),
+            Err(message) => println!("Invalid input: {}. Please try again.", message),
+        }
+    }
+}
+
+// A list of pairs on one line, e.g. "0.25,1.5; 0.75,1.5"; 'none' clears a previous list
+pub fn prompt_pairs(label: &str, format: &str, default: Option<&[(f64, f64)]>) -> Result<Vec<(f64, f64)>, Cancelled> {
+    let label = match default {
+        Some(pairs) if !pairs.is_empty() => format!("{} as {}; {}; ... ('none' to clear)", label, format, format),
+        _ => format!("{} as {}; {}; ... (Enter for none)", label, format, format),
+    };
+    let default_text = default.filter(|pairs| !pairs.is_empty()).map(|pairs| {
+        pairs.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join("; ")
+    });
+    loop {
+        let input = read(&label_with_default(&label, default_text.clone()))?;
+        if input.is_empty() {
+            return Ok(default.map(<[_]>::to_vec).unwrap_or_default());
+        }
+        if input.eq_ignore_ascii_case("none") {
+            return Ok(Vec::new());
+        }
+        match parse_pairs(&input) {
+            Ok(pairs) => return Ok(pairs),
+            Err(message) => println!("Invalid input: {}. Please try again.", message),
+        }
+    }
+}
+
+pub fn prompt_value(field: &Field, default: Option<&Value>) -> Result<Value, Cancelled> {
+    Ok(match (&field.kind, default) {
+        (FieldKind::Number, Some(Value::Number(default))) => Value::Number(prompt_number(field.label, field.rule, Some(*default))?),
+        (FieldKind::Number, _) => Value::Number(prompt_number(field.label, field.rule, None)?),
+        (FieldKind::Count, Some(Value::Count(default))) => Value::Count(prompt_count(field.label, Some(*default))?),
+        (FieldKind::Count, _) => Value::Count(prompt_count(field.label, None)?),
+        (FieldKind::CashFlows, Some(Value::CashFlows(default))) => Value::CashFlows(get_cashflows(Some(default))?),
+        (FieldKind::CashFlows, _) => Value::CashFlows(get_cashflows(None)?),
+        (FieldKind::Choice(options), Some(Value::Choice(default))) => Value::Choice(prompt_choice(field.label, options, Some(default))?),
+        (FieldKind::Choice(options), _) => Value::Choice(prompt_choice(field.label, options, None)?),
+        (FieldKind::Numbers, Some(Value::Numbers(default))) => Value::Numbers(prompt_numbers(field.label, Some(default))?),
+        (FieldKind::Numbers, _) => Value::Numbers(prompt_numbers(field.label, None)?),
+        (FieldKind::Pairs(format), Some(Value::Pairs(default))) => Value::Pairs(prompt_pairs(field.label, format, Some(default))?),
+        (FieldKind::Pairs(format), _) => Value::Pairs(prompt_pairs(field.label, format, None)?),
+        (FieldKind::Text(validate), Some(Value::Text(default))) => Value::Text(prompt_text(field.label, *validate, Some(default))?),
+        (FieldKind::Text(validate), _) => Value::Text(prompt_text(field.label, *validate, None)?),
+    })
+}
+
+// Prompts for every input of a calculator, re-asking the fields of any failed cross-field check.
+// Values in `defaults` are offered as editable defaults.
+pub fn prompt_inputs(calc: &'static Calculator, defaults: &Inputs) -> Result<Inputs, Cancelled> {
+    println!("(type '{}' at any prompt to return to the menu; use 'ans' or $name for stored results)", CANCEL);
+    println!("(numbers may be expressions such as 1250000 - 340000, 0.21*(1-0.05), 5%, 3.5m or $1,234.56)");
+    let mut inputs = Inputs::new();
+    for field in calc.fields {
+        let fallback = field.default_value();
+        inputs.push(field, prompt_value(field, defaults.value(field.name).or(fallback.as_ref()))?);
+    }
+
+    for check in calc.checks {
+        while let Err(message) = check.apply(&inputs) {
+            println!("Invalid input: {}. Please re-enter them.", message);
+            for name in check.fields() {
+                let field = calc.field(name);
+                let value = prompt_value(field, inputs.value(name))?;
+                inputs.set(field, value);
+            }
+        }
+    }
+    Ok(inputs)
+}
+
+const CASHFLOW_HELP: &str = "Cash flow commands (N is a row number):
+  AMOUNT DATE                       add a flow, e.g. -1000 2024-01-31 (any common date format)
+  insert N AMOUNT DATE              insert a flow before row N
+  edit N AMOUNT DATE                replace row N; give only AMOUNT or DATE to change one
+  delete N                          remove row N
+  repeat N monthly|quarterly|annually COUNT   add COUNT copies of row N at that frequency
+  sort                              order the flows by date
+  negate                            flip the sign of every flow, e.g. after an import
+  import FILE                       append the flows of a CSV file or bank/brokerage export
+  clear                             remove every flow
+  done                              finish (Enter also finishes)";
+
+fn print_cashflows(flows: &[CashFlow]) {
+    if flows.is_empty() {
+        println!("No cash flows yet.");
+        return;
+    }
+    println!("{:>4}  {:<10}  {:>16}", "#", "Date", "Amount");
+    for (index, (amount, date)) in flows.iter().enumerate() {
+        println!("{:>4}  {:<10}  {:>16.2}", index + 1, date, amount);
+    }
+    println!("{:>4}  {:<10}  {:>16.2}", "", "Total", flows.iter().map(|(amount, _)| amount).sum::<f64>());
+}
+
+// "AMOUNT DATE" or "AMOUNT,DATE"; the amount may be an expression and the date any format
+// `cashflows::parse_date` knows
+fn parse_flow(text: &str) -> Result<CashFlow, String> {
+    let text = text.trim();
+    let split = text.rfind([' ', ',']).ok_or_else(|| format!("expected AMOUNT DATE but got '{}'", text))?;
+    let (amount, date) = (text[..split].trim().trim_end_matches(','), text[split + 1..].trim());
+    // Dates such as "31 Jan 2024" contain spaces, so also try splitting after the amount
+    let (amount, date) = match (cashflows::parse_date(date), text.split_once([' ', ','])) {
+        (Some(date), _) => (amount.to_string(), date),
+        (None, Some((first, rest))) => match cashflows::parse_date(rest) {
+            Some(date) => (first.to_string(), date),
+            None => return Err(format!("'{}' is not a date", date)),
+        },
+        (None, None) => return Err(format!("'{}' is not a date", date)),
+    };
+    let amount = parse_number(&amount)?;
+    if !amount.is_finite() {
+        return Err("amount must be a finite number".to_string());
+    }
+    Ok((amount, date.format("%Y-%m-%d").to_string()))
+}
+
+fn is_sorted_by_date(flows: &[CashFlow]) -> bool {
+    flows.windows(2).all(|pair| pair[0].1 <= pair[1].1)
+}
+
+fn row_number(text: &str, len: usize) -> Result<usize, String> {
+    match text.parse::<usize>() {
+        Ok(row) if (1..=len).contains(&row) => Ok(row - 1),
+        _ => Err(format!("'{}' is not a row number between 1 and {}", text, len)),
+    }
+}
+
+// Applies one editor command to the table; any other input is added as a cash flow
+fn edit_cashflows(flows: &mut Vec<CashFlow>, input: &str) -> Result<(), String> {
+    let (command, rest) = input.split_once(' ').unwrap_or((input, ""));
+    let rest = rest.trim();
+    let words: Vec<&str> = rest.split_whitespace().collect();
+    match command.to_lowercase().as_str() {
+        "insert" => {
+            let (row, flow) = rest.split_once(' ').ok_or("expected insert N AMOUNT DATE")?;
+            let row = if row == (flows.len() + 1).to_string() { flows.len() } else { row_number(row, flows.len())? };
+            flows.insert(row, parse_flow(flow)?);
+        }
+        "edit" => {
+            let (row, change) = rest.split_once(' ').ok_or("expected edit N AMOUNT DATE")?;
+            let row = row_number(row, flows.len())?;
+            flows[row] = match (parse_flow(change), cashflows::parse_date(change)) {
+                (Ok(flow), _) => flow,
+                (Err(_), Some(date)) => (flows[row].0, date.format("%Y-%m-%d").to_string()),
+                (Err(_), None) => (parse_number(change.trim())?, flows[row].1.clone()),
+            };
+        }
+        "delete" => {
+            let row = row_number(rest, flows.len())?;
+            flows.remove(row);
+        }
+        "repeat" => {
+            let [row, frequency, count] = words[..] else {
+                return Err("expected repeat N monthly|quarterly|annually COUNT".to_string());
+            };
+            let row = row_number(row, flows.len())?;
+            let frequency = cashflows::Frequency::parse(frequency).ok_or_else(|| format!("'{}' is not monthly, quarterly or annually", frequency))?;
+            let count = count.parse::<u32>().map_err(|_| format!("'{}' is not a whole number", count))?;
+            let copies = cashflows::repeat(&flows[row], frequency, count)?;
+            flows.splice(row + 1..row + 1, copies);
+        }
+        "sort" => cashflows::sort_by_date(flows),
+        "negate" => flows.iter_mut().for_each(|(amount, _)| *amount = -*amount),
+        "import" => {
+            let import = cashflows::import(Path::new(rest))?;
+            println!("Imported {} cash flows from {}.", import.flows.len(), rest);
+            for reason in import.skipped.iter().take(10) {
+                println!("Skipped {}", reason);
+            }
+            if import.skipped.len() > 10 {
+                println!("... and {} more skipped rows", import.skipped.len() - 10);
+            }
+            flows.extend(import.flows);
+        }
+        "clear" => flows.clear(),
+        _ => flows.push(parse_flow(input)?),
+    }
+    Ok(())
+}
+
+// Editable table of dated cash flows, starting from `default` when re-running a calculation
+pub fn get_cashflows(default: Option<&[CashFlow]>) -> Result<Vec<CashFlow>, Cancelled> {
+    let mut flows = default.map(<[_]>::to_vec).unwrap_or_default();
+    let mut order_warned = false;
+    println!("{}", CASHFLOW_HELP);
+    loop {
+        println!();
+        print_cashflows(&flows);
+        let input = read("Enter a cash flow, a command, 'help' or 'done': ")?;
+        let command = input.split_once(' ').map_or(input.as_str(), |(command, _)| command).to_lowercase();
+        let result = match command.as_str() {
+            "" | "done" => match validate_cashflows(&flows) {
+                Ok(()) if !order_warned && !is_sorted_by_date(&flows) => {
+                    order_warned = true;
+                    println!("The flows are not in date order; type 'sort' to order them or 'done' again to keep this order.");
+                    Ok(())
+                }
+                Ok(()) => return Ok(flows),
+                Err(message) => Err(message),
+            },
+            "help" | "?" => {
+                println!("{}", CASHFLOW_HELP);
+                Ok(())
+            }
+            _ => edit_cashflows(&mut flows, &input),
+        };
+        if let Err(message) = result {
+            println!("Invalid input: {}. Please try again.", message);
+        }
+    }
+}
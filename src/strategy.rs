@@ -0,0 +1,270 @@
+use crate::options::{self, OptionKind};
+
+// Multi-leg option strategies priced with `bsm`: legs, payoff at expiry and a terminal chart
+
+#[derive(Clone, Copy)]
+pub enum Instrument {
+    Option(OptionKind, f64), // with its strike
+    Stock,
+}
+
+// Positive quantities are bought, negative ones sold
+pub struct Leg {
+    pub quantity: f64,
+    pub instrument: Instrument,
+}
+
+// A named strategy, its strike count and its legs as (quantity, instrument, strike index)
+type Template = (&'static str, usize, &'static [(f64, &'static str, usize)]);
+
+const TEMPLATES: &[Template] = &[
+    ("straddle", 1, &[(1.0, "call", 0), (1.0, "put", 0)]),
+    ("strangle", 2, &[(1.0, "put", 0), (1.0, "call", 1)]),
+    ("bull-call-spread", 2, &[(1.0, "call", 0), (-1.0, "call", 1)]),
+    ("bear-put-spread", 2, &[(-1.0, "put", 0), (1.0, "put", 1)]),
+    ("butterfly", 3, &[(1.0, "call", 0), (-2.0, "call", 1), (1.0, "call", 2)]),
+    ("condor", 4, &[(1.0, "call", 0), (-1.0, "call", 1), (-1.0, "call", 2), (1.0, "call", 3)]),
+    ("iron-condor", 4, &[(1.0, "put", 0), (-1.0, "put", 1), (-1.0, "call", 2), (1.0, "call", 3)]),
+    ("collar", 2, &[(1.0, "stock", 0), (1.0, "put", 0), (-1.0, "call", 1)]),
+];
+
+pub const LEGS_HELP: &str = "legs, e.g. '1 call 100; -1 call 110' or a template: \
+straddle K, strangle K1 K2, bull-call-spread K1 K2, bear-put-spread K1 K2, butterfly K1 K2 K3, \
+condor K1..K4, iron-condor K1..K4, collar PUT CALL";
+
+fn number(text: &str) -> Result<f64, String> {
+    text.parse::<f64>().map_err(|_| format!("'{}' is not a number", text))
+}
+
+fn instrument(name: &str, strike: Option<f64>) -> Result<Instrument, String> {
+    match (name, strike) {
+        ("stock", _) => Ok(Instrument::Stock),
+        ("call" | "put", Some(strike)) if strike > 0.0 => Ok(Instrument::Option(OptionKind::from_choice(name), strike)),
+        ("call" | "put", _) => Err(format!("{} needs a positive strike", name)),
+        _ => Err(format!("unknown instrument '{}' (use call, put or stock)", name)),
+    }
+}
+
+// Parses "QTY call|put STRIKE; QTY stock; ..." or a template such as "butterfly 90 100 110"
+pub fn parse_legs(text: &str) -> Result<Vec<Leg>, String> {
+    let text = text.trim().to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if let Some((_, strike_count, legs)) = TEMPLATES.iter().find(|(name, _, _)| Some(name) == words.first()) {
+        let strikes = words[1..].iter().map(|word| number(word)).collect::<Result<Vec<f64>, String>>()?;
+        if strikes.len() != *strike_count || strikes.iter().any(|strike| *strike <= 0.0) {
+            return Err(format!("{} needs {} positive strike(s)", words[0], strike_count));
+        }
+        if strikes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(format!("{} strikes must be in increasing order", words[0]));
+        }
+        return legs
+            .iter()
+            .map(|(quantity, name, index)| {
+                Ok(Leg { quantity: *quantity, instrument: instrument(name, Some(strikes[*index]))? })
+            })
+            .collect();
+    }
+
+    let legs = text
+        .split(';')
+        .filter(|leg| !leg.trim().is_empty())
+        .map(|leg| {
+            let words: Vec<&str> = leg.split_whitespace().collect();
+            match words.as_slice() {
+                [quantity, name] => Ok(Leg { quantity: number(quantity)?, instrument: instrument(name, None)? }),
+                [quantity, name, strike] => Ok(Leg { quantity: number(quantity)?, instrument: instrument(name, Some(number(strike)?))? }),
+                _ => Err(format!("expected 'QTY call|put STRIKE' or 'QTY stock' but got '{}'", leg.trim())),
+            }
+        })
+        .collect::<Result<Vec<Leg>, String>>()?;
+    if legs.is_empty() {
+        return Err("needs at least one leg".to_string());
+    }
+    if let Some(leg) = legs.iter().find(|leg| leg.quantity == 0.0 || !leg.quantity.is_finite()) {
+        return Err(format!("quantity {} must be a non-zero number", leg.quantity));
+    }
+    Ok(legs)
+}
+
+pub fn validate_legs(text: &str) -> Result<(), String> {
+    parse_legs(text).map(|_| ())
+}
+
+// Market data the legs are priced with
+pub struct Market {
+    pub spot: f64,
+    pub time: f64,
+    pub rate: f64,
+    pub volatility: f64,
+    pub dividend_yield: f64,
+}
+
+pub struct Analysis {
+    pub net_premium: f64, // paid when positive, received when negative
+    pub delta: f64,
+    pub gamma: f64,
+    pub vega: f64,
+    pub theta: f64,
+    pub rho: f64,
+    pub max_profit: f64, // infinite when the upside is unlimited
+    pub max_loss: f64,   // as a negative P&L; negative infinity when unlimited
+    pub breakevens: Vec<f64>,
+}
+
+fn price(leg: &Leg, market: &Market) -> f64 {
+    match leg.instrument {
+        Instrument::Stock => market.spot,
+        Instrument::Option(kind, strike) => {
+            options::bsm_price(kind, market.spot, strike, market.time, market.rate, market.volatility, market.dividend_yield)
+        }
+    }
+}
+
+fn payoff(leg: &Leg, spot: f64) -> f64 {
+    match leg.instrument {
+        Instrument::Stock => spot,
+        Instrument::Option(OptionKind::Call, strike) => (spot - strike).max(0.0),
+        Instrument::Option(OptionKind::Put, strike) => (strike - spot).max(0.0),
+    }
+}
+
+pub fn net_premium(legs: &[Leg], market: &Market) -> f64 {
+    legs.iter().map(|leg| leg.quantity * price(leg, market)).sum()
+}
+
+// Profit or loss at expiry for a final stock price, ignoring the financing of the premium
+pub fn pnl_at_expiry(legs: &[Leg], premium: f64, spot: f64) -> f64 {
+    legs.iter().map(|leg| leg.quantity * payoff(leg, spot)).sum::<f64>() - premium
+}
+
+fn strikes(legs: &[Leg]) -> Vec<f64> {
+    let mut strikes: Vec<f64> = legs
+        .iter()
+        .filter_map(|leg| match leg.instrument {
+            Instrument::Option(_, strike) => Some(strike),
+            Instrument::Stock => None,
+        })
+        .collect();
+    strikes.sort_by(f64::total_cmp);
+    strikes.dedup();
+    strikes
+}
+
+pub fn analyse(legs: &[Leg], market: &Market) -> Analysis {
+    let premium = net_premium(legs, market);
+    let mut analysis = Analysis {
+        net_premium: premium,
+        delta: 0.0,
+        gamma: 0.0,
+        vega: 0.0,
+        theta: 0.0,
+        rho: 0.0,
+        max_profit: f64::NEG_INFINITY,
+        max_loss: f64::INFINITY,
+        breakevens: Vec::new(),
+    };
+    for leg in legs {
+        match leg.instrument {
+            Instrument::Stock => analysis.delta += leg.quantity,
+            Instrument::Option(kind, strike) => {
+                let (call, put) =
+                    options::bsm_greeks(market.spot, strike, market.time, market.rate, market.volatility, market.dividend_yield);
+                let greeks = if kind == OptionKind::Call { call } else { put };
+                analysis.delta += leg.quantity * greeks.delta;
+                analysis.gamma += leg.quantity * greeks.gamma;
+                analysis.vega += leg.quantity * greeks.vega;
+                analysis.theta += leg.quantity * greeks.theta;
+                analysis.rho += leg.quantity * greeks.rho;
+            }
+        }
+    }
+
+    // The P&L is piecewise linear with kinks at the strikes, so its extremes and zeros lie at the
+    // kinks, at a zero stock price or beyond the highest strike
+    let mut points = vec![0.0];
+    points.extend(strikes(legs));
+    let pnl = |spot: f64| pnl_at_expiry(legs, premium, spot);
+    for spot in &points {
+        analysis.max_profit = analysis.max_profit.max(pnl(*spot));
+        analysis.max_loss = analysis.max_loss.min(pnl(*spot));
+    }
+    let last = points[points.len() - 1];
+    let final_slope = pnl(last + 1.0) - pnl(last);
+    if final_slope > 1e-9 {
+        analysis.max_profit = f64::INFINITY;
+    } else if final_slope < -1e-9 {
+        analysis.max_loss = f64::NEG_INFINITY;
+    }
+
+    for pair in points.windows(2) {
+        let (a, b) = (pnl(pair[0]), pnl(pair[1]));
+        if a == 0.0 {
+            analysis.breakevens.push(pair[0]);
+        } else if a * b < 0.0 {
+            analysis.breakevens.push(pair[0] + (pair[1] - pair[0]) * a / (a - b));
+        }
+    }
+    let at_last = pnl(last);
+    if at_last == 0.0 && points.len() > 1 {
+        analysis.breakevens.push(last);
+    } else if at_last * final_slope < 0.0 {
+        analysis.breakevens.push(last - at_last / final_slope);
+    }
+    analysis
+}
+
+const CHART_WIDTH: usize = 61;
+const CHART_HEIGHT: usize = 17;
+
+// P&L at expiry against the final stock price; '*' is the P&L, '-' zero and '|' today's price
+pub fn payoff_chart(legs: &[Leg], market: &Market) -> String {
+    let premium = net_premium(legs, market);
+    let strikes = strikes(legs);
+    let low_strike = strikes.first().copied().unwrap_or(market.spot).min(market.spot);
+    let high_strike = strikes.last().copied().unwrap_or(market.spot).max(market.spot);
+    let margin = 0.25 * (high_strike - low_strike).max(0.2 * market.spot);
+    let (low, high) = ((low_strike - margin).max(0.0), high_strike + margin);
+
+    let spot_at = |column: usize| low + (high - low) * column as f64 / (CHART_WIDTH - 1) as f64;
+    let values: Vec<f64> = (0..CHART_WIDTH).map(|column| pnl_at_expiry(legs, premium, spot_at(column))).collect();
+    let top = values.iter().copied().fold(0.0, f64::max);
+    let bottom = values.iter().copied().fold(0.0, f64::min);
+    let span = if top > bottom { top - bottom } else { 1.0 };
+    let row_of = |value: f64| ((top - value) / span * (CHART_HEIGHT - 1) as f64).round() as usize;
+    let spot_column = ((market.spot - low) / (high - low) * (CHART_WIDTH - 1) as f64).round() as usize;
+
+    let mut grid = vec![vec![' '; CHART_WIDTH]; CHART_HEIGHT];
+    for row in grid.iter_mut() {
+        row[spot_column] = '|';
+    }
+    for cell in grid[row_of(0.0)].iter_mut() {
+        *cell = '-';
+    }
+    for (column, value) in values.iter().enumerate() {
+        grid[row_of(*value)][column] = '*';
+    }
+
+    let mut text = String::from("\nP&L at expiry:\n");
+    for (index, row) in grid.iter().enumerate() {
+        let label = if index == 0 {
+            format!("{:>10.2}", top)
+        } else if index == CHART_HEIGHT - 1 {
+            format!("{:>10.2}", bottom)
+        } else if index == row_of(0.0) {
+            format!("{:>10.2}", 0.0)
+        } else {
+            " ".repeat(10)
+        };
+        text.push_str(&format!("{} {}\n", label, row.iter().collect::<String>()));
+    }
+    text.push_str(&format!(
+        "{} {:<w$.2}{:>w2$.2}\n",
+        " ".repeat(10),
+        low,
+        high,
+        w = CHART_WIDTH / 2,
+        w2 = CHART_WIDTH - CHART_WIDTH / 2
+    ));
+    text.push_str(&format!("{} stock price at expiry ('|' = today's {:.2})\n", " ".repeat(10), market.spot));
+    text
+}
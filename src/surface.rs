@@ -0,0 +1,307 @@
+use std::path::Path;
+
+use chrono::{Local, NaiveDate};
+
+use crate::cashflows;
+use crate::options::{self, OptionKind};
+
+// Implied volatility smiles per expiry from an option chain CSV, joined into a surface
+
+// One quote of the chain: strike, expiry, bid, ask and type columns, in any order.
+// Expiries are year fractions or YYYY-MM-DD dates.
+struct Quote {
+    line: usize,
+    strike: f64,
+    time: f64,
+    mid: f64,
+    kind: OptionKind,
+}
+
+pub struct Market {
+    pub spot: f64,
+    pub rate: f64,
+    pub dividend_yield: f64,
+    pub valuation_date: NaiveDate,
+}
+
+// Lowest vol a smile returns; a spline through sparse strikes can dip below zero between them
+const VOL_FLOOR: f64 = 0.001;
+
+// Volatility smile of one expiry: a natural cubic spline through (strike, vol), flat outside
+struct Smile {
+    time: f64,
+    strikes: Vec<f64>,
+    vols: Vec<f64>,
+    second_derivatives: Vec<f64>,
+}
+
+pub struct Surface {
+    smiles: Vec<Smile>, // by increasing expiry
+    pub quotes_used: usize,
+    pub rejected: Vec<String>, // "line N: reason" for quotes that could not be inverted
+}
+
+// 'today' or a YYYY-MM-DD date
+pub fn parse_valuation_date(text: &str) -> Result<NaiveDate, String> {
+    if text.trim().eq_ignore_ascii_case("today") {
+        return Ok(Local::now().date_naive());
+    }
+    cashflows::parse_iso_date(text).ok_or_else(|| format!("'{}' is not 'today' or a YYYY-MM-DD date", text.trim()))
+}
+
+pub fn validate_valuation_date(text: &str) -> Result<(), String> {
+    parse_valuation_date(text).map(|_| ())
+}
+
+pub fn validate_chain_file(text: &str) -> Result<(), String> {
+    if Path::new(text.trim()).is_file() {
+        return Ok(());
+    }
+    Err(format!("'{}' is not a readable file", text.trim()))
+}
+
+fn column(headers: &csv::StringRecord, names: &[&str]) -> Result<usize, String> {
+    headers
+        .iter()
+        .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+        .ok_or_else(|| format!("the chain has no '{}' column", names[0]))
+}
+
+fn parse_quote(record: &csv::StringRecord, columns: &[usize; 5], valuation_date: NaiveDate) -> Result<(f64, f64, f64, f64, OptionKind), String> {
+    let cell = |index: usize| record.get(columns[index]).unwrap_or("").trim();
+    let number = |index: usize, name: &str| cell(index).parse::<f64>().map_err(|_| format!("invalid {} '{}'", name, cell(index)));
+    let strike = number(0, "strike")?;
+    let time = match cell(1).parse::<f64>() {
+        Ok(years) => years,
+        Err(_) => {
+            let expiry = cashflows::parse_iso_date(cell(1)).ok_or_else(|| format!("invalid expiry '{}'", cell(1)))?;
+            (expiry - valuation_date).num_days() as f64 / 365.0
+        }
+    };
+    let bid = number(2, "bid")?;
+    let ask = number(3, "ask")?;
+    let kind = match cell(4).to_lowercase().as_str() {
+        "call" | "c" => OptionKind::Call,
+        "put" | "p" => OptionKind::Put,
+        other => return Err(format!("invalid type '{}'", other)),
+    };
+    Ok((strike, time, bid, ask, kind))
+}
+
+fn read_chain(path: &Path, valuation_date: NaiveDate) -> Result<(Vec<Quote>, Vec<String>), String> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let headers = reader.headers().map_err(|e| format!("{}: {}", path.display(), e))?.clone();
+    let columns = [
+        column(&headers, &["strike"])?,
+        column(&headers, &["expiry", "expiration", "maturity"])?,
+        column(&headers, &["bid"])?,
+        column(&headers, &["ask"])?,
+        column(&headers, &["type", "option_type", "cp"])?,
+    ];
+
+    let mut quotes = Vec::new();
+    let mut rejected = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let line = index + 2;
+        let quote = record
+            .map_err(|e| e.to_string())
+            .and_then(|record| parse_quote(&record, &columns, valuation_date))
+            .and_then(|(strike, time, bid, ask, kind)| {
+                if strike <= 0.0 || time <= 0.0 {
+                    return Err("strike and time to expiry must be positive".to_string());
+                }
+                if bid < 0.0 || ask <= 0.0 || ask < bid {
+                    return Err(format!("bid {} / ask {} is not a valid market", bid, ask));
+                }
+                Ok(Quote { line, strike, time, mid: 0.5 * (bid + ask), kind })
+            });
+        match quote {
+            Ok(quote) => quotes.push(quote),
+            Err(message) => rejected.push(format!("line {}: {}", line, message)),
+        }
+    }
+    Ok((quotes, rejected))
+}
+
+impl Smile {
+    fn new(time: f64, mut points: Vec<(f64, f64)>) -> Smile {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        // Calls and puts at the same strike are averaged
+        let mut merged: Vec<(f64, f64, usize)> = Vec::new();
+        for (strike, vol) in points {
+            match merged.last_mut() {
+                Some(last) if (last.0 - strike).abs() < 1e-9 => {
+                    last.1 += vol;
+                    last.2 += 1;
+                }
+                _ => merged.push((strike, vol, 1)),
+            }
+        }
+        let strikes: Vec<f64> = merged.iter().map(|point| point.0).collect();
+        let vols: Vec<f64> = merged.iter().map(|point| point.1 / point.2 as f64).collect();
+        let second_derivatives = natural_spline(&strikes, &vols);
+        Smile { time, strikes, vols, second_derivatives }
+    }
+
+    fn vol(&self, strike: f64) -> f64 {
+        let n = self.strikes.len();
+        if n == 1 || strike <= self.strikes[0] {
+            return self.vols[0];
+        }
+        if strike >= self.strikes[n - 1] {
+            return self.vols[n - 1];
+        }
+        let i = self.strikes.partition_point(|k| *k <= strike).min(n - 1) - 1;
+        let (x0, x1) = (self.strikes[i], self.strikes[i + 1]);
+        let h = x1 - x0;
+        let a = (x1 - strike) / h;
+        let b = (strike - x0) / h;
+        let vol = a * self.vols[i]
+            + b * self.vols[i + 1]
+            + ((a.powi(3) - a) * self.second_derivatives[i] + (b.powi(3) - b) * self.second_derivatives[i + 1]) * h * h / 6.0;
+        vol.max(VOL_FLOOR)
+    }
+}
+
+// Second derivatives of the natural cubic spline through (xs, ys), by the tridiagonal algorithm
+fn natural_spline(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let n = xs.len();
+    let mut second = vec![0.0; n];
+    if n < 3 {
+        return second;
+    }
+    let mut upper = vec![0.0; n];
+    for i in 1..n - 1 {
+        let sig = (xs[i] - xs[i - 1]) / (xs[i + 1] - xs[i - 1]);
+        let p = sig * second[i - 1] + 2.0;
+        second[i] = (sig - 1.0) / p;
+        let slope_change = (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]) - (ys[i] - ys[i - 1]) / (xs[i] - xs[i - 1]);
+        upper[i] = (6.0 * slope_change / (xs[i + 1] - xs[i - 1]) - sig * upper[i - 1]) / p;
+    }
+    second[n - 1] = 0.0;
+    for i in (0..n - 1).rev() {
+        second[i] = second[i] * second[i + 1] + upper[i];
+    }
+    second
+}
+
+impl Surface {
+    // Reads the chain, inverts each mid price with `bsm` and fits a smile per expiry
+    pub fn build(path: &Path, market: &Market) -> Result<Surface, String> {
+        let (quotes, mut rejected) = read_chain(path, market.valuation_date)?;
+        let mut slices: Vec<(f64, Vec<(f64, f64)>)> = Vec::new();
+        for quote in &quotes {
+            let vol = options::bsm_implied_vol(quote.kind, quote.mid, market.spot, quote.strike, quote.time, market.rate, market.dividend_yield);
+            match vol {
+                Ok(solution) => match slices.iter_mut().find(|(time, _)| (time - quote.time).abs() < 1e-9) {
+                    Some((_, points)) => points.push((quote.strike, solution.volatility)),
+                    None => slices.push((quote.time, vec![(quote.strike, solution.volatility)])),
+                },
+                Err(message) => rejected.push(format!("line {}: {}", quote.line, message)),
+            }
+        }
+        if slices.is_empty() {
+            return Err(format!("no quote in {} could be inverted to an implied volatility", path.display()));
+        }
+        slices.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let quotes_used = slices.iter().map(|(_, points)| points.len()).sum();
+        let smiles = slices.into_iter().map(|(time, points)| Smile::new(time, points)).collect();
+        Ok(Surface { smiles, quotes_used, rejected })
+    }
+
+    pub fn expiries(&self) -> usize {
+        self.smiles.len()
+    }
+
+    // Volatility at a strike and time: the smiles are interpolated linearly in total variance
+    // between expiries and held flat before the first and after the last
+    pub fn vol(&self, strike: f64, time: f64) -> Result<f64, String> {
+        let first = &self.smiles[0];
+        let last = &self.smiles[self.smiles.len() - 1];
+        let vol = if time <= first.time {
+            first.vol(strike)
+        } else if time >= last.time {
+            last.vol(strike)
+        } else {
+            let i = self.smiles.partition_point(|smile| smile.time <= time) - 1;
+            let (near, far) = (&self.smiles[i], &self.smiles[i + 1]);
+            let near_variance = near.vol(strike).powi(2) * near.time;
+            let far_variance = far.vol(strike).powi(2) * far.time;
+            let variance = near_variance + (far_variance - near_variance) * (time - near.time) / (far.time - near.time);
+            (variance / time).sqrt()
+        };
+        if !vol.is_finite() || vol <= 0.0 {
+            return Err(format!("the surface has no positive volatility at strike {} and time {}", strike, time));
+        }
+        Ok(vol)
+    }
+
+    // Expiries down, strikes across, vols in percent
+    pub fn grid(&self) -> String {
+        const COLUMNS: usize = 9;
+        let low = self.smiles.iter().map(|smile| smile.strikes[0]).fold(f64::MAX, f64::min);
+        let high = self.smiles.iter().map(|smile| smile.strikes[smile.strikes.len() - 1]).fold(f64::MIN, f64::max);
+        let strikes: Vec<f64> = if high > low {
+            (0..COLUMNS).map(|i| low + (high - low) * i as f64 / (COLUMNS - 1) as f64).collect()
+        } else {
+            vec![low]
+        };
+
+        let mut text = String::from("\nImplied volatility surface (%), expiry in years down, strike across:\n");
+        text.push_str(&format!("{:>8}", "T \\ K"));
+        for strike in &strikes {
+            text.push_str(&format!("{:>9.2}", strike));
+        }
+        text.push('\n');
+        for smile in &self.smiles {
+            text.push_str(&format!("{:>8.3}", smile.time));
+            for strike in &strikes {
+                match self.vol(*strike, smile.time) {
+                    Ok(vol) => text.push_str(&format!("{:>9.2}", 100.0 * vol)),
+                    Err(_) => text.push_str(&format!("{:>9}", "-")),
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    #[test]
+    fn surface_reproduces_quotes_at_its_nodes() {
+        let market = Market { spot: 100.0, rate: 0.03, dividend_yield: 0.01, valuation_date: NaiveDate::from_ymd_opt(2026, 1, 2).unwrap() };
+        // (expiry cell, years, strike, vol, type); the second expiry is 364 days out
+        let nodes = [
+            ("0.25", 0.25, 90.0, 0.28, OptionKind::Put),
+            ("0.25", 0.25, 100.0, 0.22, OptionKind::Call),
+            ("0.25", 0.25, 110.0, 0.20, OptionKind::Call),
+            ("2027-01-01", 364.0 / 365.0, 80.0, 0.30, OptionKind::Put),
+            ("2027-01-01", 364.0 / 365.0, 100.0, 0.24, OptionKind::Call),
+            ("2027-01-01", 364.0 / 365.0, 120.0, 0.21, OptionKind::Call),
+        ];
+        let mut chain = String::from("Type,Strike,Expiry,Bid,Ask\n");
+        for (expiry, time, strike, vol, kind) in nodes {
+            let price = options::bsm_price(kind, market.spot, strike, time, market.rate, vol, market.dividend_yield);
+            let kind = if kind == OptionKind::Call { "C" } else { "P" };
+            chain.push_str(&format!("{},{},{},{:.10},{:.10}\n", kind, strike, expiry, price - 0.01, price + 0.01));
+        }
+        chain.push_str("C,105,0.25,2.0,1.0\n");
+        let path = std::env::temp_dir().join(format!("surface_nodes_{}.csv", std::process::id()));
+        std::fs::write(&path, chain).unwrap();
+        let surface = Surface::build(&path, &market);
+        std::fs::remove_file(&path).unwrap();
+
+        let surface = surface.unwrap();
+        assert_eq!(surface.expiries(), 2);
+        assert_eq!(surface.quotes_used, 6);
+        assert_eq!(surface.rejected, vec!["line 8: bid 2 / ask 1 is not a valid market".to_string()]);
+        for (_, time, strike, vol, _) in nodes {
+            assert_close(surface.vol(strike, time).unwrap(), vol, 1e-6);
+        }
+    }
+}
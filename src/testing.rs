@@ -0,0 +1,6 @@
+// Helpers shared by the unit tests
+
+#[track_caller]
+pub fn assert_close(value: f64, expected: f64, tolerance: f64) {
+    assert!((value - expected).abs() <= tolerance, "{} is not within {} of {}", value, tolerance, expected);
+}
@@ -0,0 +1,150 @@
+use crate::irr;
+
+// Five-key time value of money with the sign convention of financial calculators: money received
+// is positive and money paid out negative, so PV + PMT x annuity factor + FV x discount = 0
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Timing {
+    End,
+    Begin, // payments at the start of each period, as for leases and rent
+}
+
+impl Timing {
+    pub fn from_choice(choice: &str) -> Timing {
+        if choice == "begin" { Timing::Begin } else { Timing::End }
+    }
+}
+
+// Rate, payment growth and timing per payment period
+#[derive(Clone, Copy)]
+pub struct Terms {
+    pub rate: f64,
+    pub growth: f64,
+    pub timing: Timing,
+}
+
+// Rate per payment period equivalent to a nominal annual rate compounded `compounding` times a
+// year, for `payments` payments a year
+pub fn periodic_rate(nominal: f64, compounding: f64, payments: f64) -> Result<f64, String> {
+    let base = 1.0 + nominal / compounding;
+    if base <= 0.0 {
+        return Err(format!("a nominal rate of {} compounded {} times a year is below -100%", nominal, compounding));
+    }
+    Ok(base.powf(compounding / payments) - 1.0)
+}
+
+pub fn nominal_rate(periodic: f64, compounding: f64, payments: f64) -> f64 {
+    compounding * ((1.0 + periodic).powf(payments / compounding) - 1.0)
+}
+
+pub fn effective_annual_rate(periodic: f64, payments: f64) -> f64 {
+    (1.0 + periodic).powf(payments) - 1.0
+}
+
+// Value today of `n` payments starting at 1 and growing by `terms.growth` a period
+fn annuity_factor(n: f64, terms: Terms) -> f64 {
+    let Terms { rate, growth, timing } = terms;
+    let factor = if (rate - growth).abs() < 1e-12 {
+        n / (1.0 + rate)
+    } else {
+        (1.0 - ((1.0 + growth) / (1.0 + rate)).powf(n)) / (rate - growth)
+    };
+    if timing == Timing::Begin { factor * (1.0 + rate) } else { factor }
+}
+
+// Zero when the five values are consistent
+fn balance(pv: f64, pmt: f64, fv: f64, n: f64, terms: Terms) -> f64 {
+    pv + pmt * annuity_factor(n, terms) + fv / (1.0 + terms.rate).powf(n)
+}
+
+pub fn present_value(pmt: f64, fv: f64, n: f64, terms: Terms) -> f64 {
+    -balance(0.0, pmt, fv, n, terms)
+}
+
+pub fn future_value(pv: f64, pmt: f64, n: f64, terms: Terms) -> f64 {
+    -balance(pv, pmt, 0.0, n, terms) * (1.0 + terms.rate).powf(n)
+}
+
+pub fn payment(pv: f64, fv: f64, n: f64, terms: Terms) -> Result<f64, String> {
+    let factor = annuity_factor(n, terms);
+    if factor == 0.0 || !factor.is_finite() {
+        return Err("no payment solves these terms".to_string());
+    }
+    Ok(-balance(pv, 0.0, fv, n, terms) / factor)
+}
+
+// Number of periods, possibly fractional; the first crossing is taken when payments grow
+pub fn periods(pv: f64, pmt: f64, fv: f64, terms: Terms) -> Result<f64, String> {
+    let f = |n: f64| balance(pv, pmt, fv, n, terms);
+    let mut low = 0.0;
+    let mut high = 1.0;
+    while f(low) * f(high) > 0.0 {
+        if high >= 1e6 {
+            return Err("no number of periods up to 1,000,000 solves these values; check the signs of PV, PMT and FV".to_string());
+        }
+        low = high;
+        high *= 2.0;
+    }
+    if f(low) == 0.0 {
+        return Ok(low);
+    }
+    Ok(irr::brent(f, low, high).rate)
+}
+
+// Rate per period; the lowest when several rates balance the values
+pub fn rate(pv: f64, pmt: f64, fv: f64, n: f64, growth: f64, timing: Timing) -> Result<f64, String> {
+    irr::find_roots(|rate| balance(pv, pmt, fv, n, Terms { rate, growth, timing }))
+        .first()
+        .map(|root| root.rate)
+        .ok_or_else(|| "no rate between -99% and 1000% per period solves these values; check the signs of PV, PMT and FV".to_string())
+}
+
+// Value today of payments growing forever, which needs a rate above the growth rate
+pub fn perpetuity(pmt: f64, terms: Terms) -> Result<f64, String> {
+    if terms.rate - terms.growth <= 1e-12 {
+        return Err("a growing perpetuity needs a periodic rate above the growth rate".to_string());
+    }
+    let value = -pmt / (terms.rate - terms.growth);
+    Ok(if terms.timing == Timing::Begin { value * (1.0 + terms.rate) } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    fn terms(rate: f64) -> Terms {
+        Terms { rate, growth: 0.0, timing: Timing::End }
+    }
+
+    #[test]
+    fn five_keys() {
+        // 30-year mortgage of 300,000 at 6% a year paid monthly
+        assert_close(payment(300_000.0, 0.0, 360.0, terms(0.005)).unwrap(), -1798.65, 0.005);
+        assert_close(future_value(-100.0, 0.0, 10.0, terms(0.05)), 162.89, 0.005);
+        assert_close(present_value(-100.0, 0.0, 5.0, terms(0.10)), 379.08, 0.005);
+        assert_close(present_value(-100.0, 0.0, 5.0, Terms { timing: Timing::Begin, ..terms(0.10) }), 416.99, 0.005);
+        assert_close(rate(-1000.0, 0.0, 2000.0, 10.0, 0.0, Timing::End).unwrap(), 0.0717735, 1e-7);
+        assert_close(periods(-1000.0, 0.0, 2000.0, terms(0.072)).unwrap(), 9.9696, 1e-4);
+        assert!(periods(-1000.0, 0.0, -2000.0, terms(0.05)).is_err());
+    }
+
+    #[test]
+    fn growing_payments() {
+        let growing = Terms { rate: 0.08, growth: 0.03, timing: Timing::End };
+        assert_close(present_value(-100.0, 0.0, 10.0, growing), 755.01, 0.005);
+        // At a rate equal to the growth every payment is worth 1 / (1 + rate) today
+        assert_close(present_value(-100.0, 0.0, 10.0, Terms { growth: 0.08, ..growing }), 1000.0 / 1.08, 1e-9);
+        assert_close(perpetuity(-5.0, Terms { rate: 0.10, growth: 0.02, timing: Timing::End }).unwrap(), 62.5, 1e-9);
+        assert!(perpetuity(-5.0, Terms { rate: 0.02, growth: 0.02, timing: Timing::End }).is_err());
+    }
+
+    #[test]
+    fn rate_conversions() {
+        assert_close(periodic_rate(0.12, 12.0, 12.0).unwrap(), 0.01, 1e-12);
+        assert_close(periodic_rate(0.12, 1.0, 12.0).unwrap(), 0.0094888, 1e-7);
+        assert_close(nominal_rate(0.0094888, 1.0, 12.0), 0.12, 1e-6);
+        assert_close(effective_annual_rate(0.01, 12.0), 0.126825, 1e-6);
+        assert!(periodic_rate(-2.0, 1.0, 12.0).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::calculators::Output;
+use crate::{clear_terminal, display_ascii_art, get_user_input};
+
+// Named variables usable at any numeric prompt as `$name`, plus `ans` for the last result.
+// A variable holds every output of the calculation it was saved from: `$name` is the first
+// output and `$name.output` picks a specific one, e.g. `$opt.put_price`.
+
+pub const ANS: &str = "ans";
+
+type Values = Vec<(String, f64)>;
+
+static VARIABLES: Lazy<Mutex<BTreeMap<String, Values>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+static LAST_OUTPUTS: Lazy<Mutex<Values>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn values_of(outputs: &[Output]) -> Values {
+    outputs.iter().map(|output| (output.name.to_string(), output.value)).collect()
+}
+
+pub fn set_last(outputs: &[Output]) {
+    *LAST_OUTPUTS.lock().unwrap() = values_of(outputs);
+}
+
+pub fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != ANS
+}
+
+pub fn save(name: &str, values: Values) {
+    VARIABLES.lock().unwrap().insert(name.to_string(), values);
+}
+
+fn pick(values: &[(String, f64)], output: Option<&str>) -> Option<f64> {
+    match output {
+        None => values.first().map(|(_, value)| *value),
+        Some(output) => values.iter().find(|(name, _)| name == output).map(|(_, value)| *value),
+    }
+}
+
+// Looks up `ans`, `ans.output`, `name` or `name.output` (without the leading `$`)
+pub fn lookup(reference: &str) -> Result<f64, String> {
+    let (name, output) = match reference.split_once('.') {
+        Some((name, output)) => (name, Some(output)),
+        None => (reference, None),
+    };
+    let value = if name == ANS {
+        let last = LAST_OUTPUTS.lock().unwrap();
+        if last.is_empty() {
+            return Err("there is no previous result for 'ans' yet".to_string());
+        }
+        pick(&last, output)
+    } else {
+        let variables = VARIABLES.lock().unwrap();
+        let values = variables
+            .get(name)
+            .ok_or_else(|| format!("unknown variable '${}'", name))?;
+        pick(values, output)
+    };
+    value.ok_or_else(|| format!("'{}' has no output '{}'", name, output.unwrap_or_default()))
+}
+
+// Offers to save the outputs of the calculation that just ran under a name
+pub fn offer_save(outputs: &[Output]) {
+    loop {
+        println!("Save result as variable (name, or Enter to skip): ");
+        let name = get_user_input().trim().trim_start_matches('$').to_string();
+        if name.is_empty() {
+            return;
+        }
+        if !valid_name(&name) {
+            println!("Invalid name: use letters, digits and '_' (not starting with a digit, not 'ans').");
+            continue;
+        }
+        save(&name, values_of(outputs));
+        if outputs.len() > 1 {
+            let names: Vec<String> = outputs.iter().map(|output| format!("${}.{}", name, output.name)).collect();
+            println!("Saved ${} (also {})", name, names.join(", "));
+        } else {
+            println!("Saved ${}", name);
+        }
+        return;
+    }
+}
+
+// Variables screen: list, add and delete variables
+pub fn display_variables() {
+    loop {
+        clear_terminal();
+        display_ascii_art();
+        println!("\nVariables (use as $name at any numeric prompt, 'ans' is the last result):");
+        {
+            let variables = VARIABLES.lock().unwrap();
+            if variables.is_empty() {
+                println!("  (none yet)");
+            }
+            for (name, values) in variables.iter() {
+                let values: Vec<String> = values.iter().map(|(output, value)| format!("{} = {}", output, value)).collect();
+                println!("  ${:<12} {}", name, values.join(", "));
+            }
+        }
+        println!("--------------");
+        println!("a. Add variable");
+        println!("d. Delete variable");
+        println!("b. Back");
+        print!("Enter your choice: ");
+        io::stdout().flush().unwrap();
+
+        match get_user_input().trim() {
+            "a" => add_variable(),
+            "d" => {
+                println!("Enter variable name to delete: ");
+                let name = get_user_input().trim().trim_start_matches('$').to_string();
+                VARIABLES.lock().unwrap().remove(&name);
+            }
+            "b" => break,
+            _ => {}
+        }
+    }
+}
+
+fn add_variable() {
+    println!("Enter variable name: ");
+    let name = get_user_input().trim().trim_start_matches('$').to_string();
+    if !valid_name(&name) {
+        return;
+    }
+    println!("Enter value: ");
+    let input = get_user_input();
+    match crate::prompt::parse_number(input.trim()) {
+        Ok(value) => save(&name, vec![("value".to_string(), value)]),
+        Err(message) => {
+            println!("Invalid input: {}. Press Enter to continue...", message);
+            let _ = get_user_input();
+        }
+    }
+}